@@ -0,0 +1,520 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder};
+use xz2::{read::XzDecoder, write::XzEncoder};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    TarXz,
+    Zip,
+}
+
+#[derive(Clone)]
+pub struct ArchiveEntry {
+    // relative to the archive root, without trailing slash
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+pub fn kind_of(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+        Some(ArchiveKind::TarXz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".zip") || name.ends_with(".jar") {
+        Some(ArchiveKind::Zip)
+    } else {
+        None
+    }
+}
+
+// "/x/a.zip/inner/dir" -> ("/x/a.zip", "inner/dir")
+pub fn split_path(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    for archive in path.ancestors() {
+        if kind_of(archive).is_some() && archive.is_file() {
+            let inner = path.strip_prefix(archive).ok()?.to_path_buf();
+            return Some((archive.to_path_buf(), inner));
+        }
+    }
+    None
+}
+
+fn tar_reader(archive: &Path, kind: ArchiveKind) -> io::Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(File::open(archive)?);
+    let reader: Box<dyn Read> = match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(file)),
+        ArchiveKind::TarXz => Box::new(XzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+fn zip_reader(archive: &Path) -> io::Result<zip::ZipArchive<BufReader<File>>> {
+    zip::ZipArchive::new(BufReader::new(File::open(archive)?)).map_err(io::Error::other)
+}
+
+// entry paths may contain "./" or be absolute, keep only the normal parts
+fn clean_path(path: &Path) -> Option<PathBuf> {
+    let mut res = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(c) => res.push(c),
+            Component::CurDir | Component::RootDir => {}
+            _ => return None,
+        }
+    }
+    (!res.as_os_str().is_empty()).then_some(res)
+}
+
+// dest joined with rel, missing folders on the way are made. a link on the
+// way is refused, an earlier entry of the same archive may point it anywhere
+fn prepare_out(dest: &Path, rel: &Path) -> io::Result<PathBuf> {
+    let mut out = dest.to_path_buf();
+    let mut components = rel.components().peekable();
+    while let Some(component) = components.next() {
+        out.push(component);
+        match out.symlink_metadata() {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("{} goes through a link, not extracted", rel.display()),
+                ));
+            }
+            Ok(_) => {}
+            Err(_) if components.peek().is_some() => fs::create_dir(&out)?,
+            Err(_) => {}
+        }
+    }
+    Ok(out)
+}
+
+// every entry of the archive, folders that only exist implicitly included
+pub fn list(archive: &Path) -> io::Result<Vec<ArchiveEntry>> {
+    let kind = kind_of(archive)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an archive"))?;
+    let mut entries: BTreeMap<PathBuf, bool> = BTreeMap::new();
+
+    if kind == ArchiveKind::Zip {
+        let mut zip = zip_reader(archive)?;
+        for i in 0..zip.len() {
+            let file = zip.by_index(i).map_err(io::Error::other)?;
+            if let Some(path) = file.enclosed_name().as_deref().and_then(clean_path) {
+                entries.insert(path, file.is_dir());
+            }
+        }
+    } else {
+        let mut tar = tar_reader(archive, kind)?;
+        for entry in tar.entries()? {
+            let entry = entry?;
+            let is_dir = entry.header().entry_type().is_dir();
+            if let Some(path) = clean_path(&entry.path()?) {
+                entries.insert(path, is_dir);
+            }
+        }
+    }
+
+    let implied: Vec<PathBuf> = entries
+        .keys()
+        .flat_map(|p| p.ancestors().skip(1).map(|a| a.to_path_buf()))
+        .filter(|a| !a.as_os_str().is_empty())
+        .collect();
+    for dir in implied {
+        entries.entry(dir).or_insert(true);
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|(path, is_dir)| ArchiveEntry { path, is_dir })
+        .collect())
+}
+
+// archive contents read once and kept per archive path, so moving around
+// inside a big archive doesn't decompress it again for every folder
+pub struct Listings {
+    // entries and the archive mtime they were read at
+    cache: HashMap<PathBuf, (Vec<ArchiveEntry>, Option<SystemTime>)>,
+}
+
+impl Listings {
+    pub fn new() -> Listings {
+        Listings {
+            cache: HashMap::new(),
+        }
+    }
+
+    // direct children of `inner`, the archive is read again once it changed
+    pub fn list_dir(&mut self, archive: &Path, inner: &Path) -> io::Result<Vec<ArchiveEntry>> {
+        let modified = fs::metadata(archive)?.modified().ok();
+        let fresh = self
+            .cache
+            .get(archive)
+            .is_some_and(|(_, mtime)| mtime.is_some() && *mtime == modified);
+        if !fresh {
+            self.cache.insert(archive.to_path_buf(), (list(archive)?, modified));
+        }
+
+        let (entries, _) = &self.cache[archive];
+        Ok(entries
+            .iter()
+            .filter(|e| e.path.parent() == Some(inner))
+            .cloned()
+            .collect())
+    }
+}
+
+// extract `inner` (a file or a whole folder) into dest, returns the created path
+pub fn extract_entry(archive: &Path, inner: &Path, dest: &Path) -> io::Result<PathBuf> {
+    let kind = kind_of(archive)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an archive"))?;
+    let base = inner.parent().unwrap_or(Path::new(""));
+    let target = dest.join(inner.strip_prefix(base).unwrap_or(inner));
+
+    if target.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+
+    let mut found = false;
+    if kind == ArchiveKind::Zip {
+        let mut zip = zip_reader(archive)?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i).map_err(io::Error::other)?;
+            let Some(path) = file.enclosed_name().as_deref().and_then(clean_path) else {
+                continue;
+            };
+            let Ok(rel) = path.strip_prefix(base) else {
+                continue;
+            };
+            if !path.starts_with(inner) {
+                continue;
+            }
+
+            found = true;
+            let out = prepare_out(dest, rel)?;
+            if file.is_dir() {
+                fs::create_dir_all(&out)?;
+            } else {
+                io::copy(&mut file, &mut File::create(&out)?)?;
+                if let Some(mode) = file.unix_mode() {
+                    fs::set_permissions(&out, fs::Permissions::from_mode(mode))?;
+                }
+            }
+        }
+    } else {
+        let mut tar = tar_reader(archive, kind)?;
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let Some(path) = clean_path(&entry.path()?) else {
+                continue;
+            };
+            if !path.starts_with(inner) {
+                continue;
+            }
+            let Ok(rel) = path.strip_prefix(base) else {
+                continue;
+            };
+
+            found = true;
+            entry.unpack(prepare_out(dest, rel)?)?;
+        }
+    }
+
+    if !found {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found in archive", inner.display()),
+        ));
+    }
+    Ok(target)
+}
+
+// pack sources into a new archive, the format comes from its extension.
+// `progress` gets every file as it is added
+pub fn create(
+    archive: &Path,
+    sources: &[PathBuf],
+    progress: &mut dyn FnMut(&Path),
+) -> io::Result<()> {
+    let kind = kind_of(archive).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unknown archive type, use .tar, .tar.gz, .tar.xz or .zip",
+        )
+    })?;
+    if archive.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", archive.display()),
+        ));
+    }
+
+    // the archive may be written inside one of the sources
+    let entries: Vec<(PathBuf, PathBuf)> = walk_sources(sources)?
+        .into_iter()
+        .filter(|(path, _)| path != archive)
+        .collect();
+
+    let file = File::create(archive)?;
+    let r = match kind {
+        ArchiveKind::Zip => write_zip(file, &entries, progress),
+        ArchiveKind::Tar => write_tar(file, &entries, progress).map(|_| ()),
+        ArchiveKind::TarGz => {
+            let encoder = GzEncoder::new(file, flate2::Compression::default());
+            write_tar(encoder, &entries, progress).and_then(|e| e.finish().map(|_| ()))
+        }
+        ArchiveKind::TarXz => {
+            let encoder = XzEncoder::new(file, 6);
+            write_tar(encoder, &entries, progress).and_then(|e| e.finish().map(|_| ()))
+        }
+    };
+
+    // don't leave half written archives behind
+    if r.is_err() {
+        let _ = fs::remove_file(archive);
+    }
+    r
+}
+
+// every file under the sources, paired with its name inside the archive
+fn walk_sources(sources: &[PathBuf]) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut res = Vec::new();
+    let mut stack: Vec<(PathBuf, PathBuf)> = sources
+        .iter()
+        .map(|s| (s.clone(), PathBuf::from(s.file_name().unwrap_or_default())))
+        .collect();
+
+    while let Some((path, name)) = stack.pop() {
+        let meta = fs::symlink_metadata(&path)?;
+        if meta.is_dir() {
+            for entry in fs::read_dir(&path)? {
+                let entry = entry?;
+                stack.push((entry.path(), name.join(entry.file_name())));
+            }
+        }
+        res.push((path, name));
+    }
+
+    res.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(res)
+}
+
+fn write_tar<W: Write>(
+    writer: W,
+    entries: &[(PathBuf, PathBuf)],
+    progress: &mut dyn FnMut(&Path),
+) -> io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+
+    for (path, name) in entries.iter() {
+        progress(path);
+        builder.append_path_with_name(path, name)?;
+    }
+    builder.into_inner()
+}
+
+fn write_zip(
+    file: File,
+    entries: &[(PathBuf, PathBuf)],
+    progress: &mut dyn FnMut(&Path),
+) -> io::Result<()> {
+    let mut zip = zip::ZipWriter::new(file);
+
+    for (path, name) in entries.iter() {
+        progress(path);
+        let meta = fs::symlink_metadata(path)?;
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .unix_permissions(meta.permissions().mode())
+            .large_file(meta.len() >= u32::MAX as u64);
+        let name = name.to_string_lossy();
+
+        if meta.is_dir() {
+            zip.add_directory(name, options).map_err(io::Error::other)?;
+        } else if meta.file_type().is_symlink() {
+            let target = fs::read_link(path)?;
+            zip.add_symlink(name, target.to_string_lossy(), options)
+                .map_err(io::Error::other)?;
+        } else {
+            zip.start_file(name, options).map_err(io::Error::other)?;
+            io::copy(&mut File::open(path)?, &mut zip)?;
+        }
+    }
+
+    zip.finish().map_err(io::Error::other)?;
+    Ok(())
+}
+
+// unpack the whole archive into dest, returns how many entries were written.
+// nothing is written when an entry would replace something in dest,
+// folders that already exist are merged
+pub fn extract_all(
+    archive: &Path,
+    dest: &Path,
+    progress: &mut dyn FnMut(&Path),
+) -> io::Result<usize> {
+    let kind = kind_of(archive)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an archive"))?;
+
+    let clashes: Vec<PathBuf> = list(archive)?
+        .into_iter()
+        .filter(|e| {
+            dest.join(&e.path)
+                .symlink_metadata()
+                .is_ok_and(|m| !(e.is_dir && m.is_dir()))
+        })
+        .map(|e| e.path)
+        .collect();
+    if let Some(first) = clashes.first() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{} entries already exist in {}, like {}",
+                clashes.len(),
+                dest.display(),
+                first.display()
+            ),
+        ));
+    }
+
+    fs::create_dir_all(dest)?;
+    let mut count = 0;
+
+    if kind == ArchiveKind::Zip {
+        let mut zip = zip_reader(archive)?;
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i).map_err(io::Error::other)?;
+            let Some(path) = file.enclosed_name().as_deref().and_then(clean_path) else {
+                continue;
+            };
+
+            progress(&path);
+            let out = prepare_out(dest, &path)?;
+            if file.is_dir() {
+                fs::create_dir_all(&out)?;
+            } else {
+                io::copy(&mut file, &mut File::create(&out)?)?;
+                if let Some(mode) = file.unix_mode() {
+                    fs::set_permissions(&out, fs::Permissions::from_mode(mode))?;
+                }
+            }
+            count += 1;
+        }
+    } else {
+        let mut tar = tar_reader(archive, kind)?;
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            progress(&entry.path()?);
+            // unpack_in refuses paths that escape dest
+            if entry.unpack_in(dest)? {
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    // dir/, dir/link -> outside, dir/link/pwned and dir/../escaped
+    fn hostile_tar(dir: &TempDir) -> PathBuf {
+        let path = dir.join("evil.tar");
+        let mut builder = tar::Builder::new(File::create(&path).unwrap());
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder.append_data(&mut header, "dir", io::empty()).unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "dir/link", dir.join("outside"))
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(1);
+        builder.append_data(&mut header, "dir/link/pwned", &b"x"[..]).unwrap();
+
+        // set_path refuses "..", so the name is written by hand
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(1);
+        let name = b"dir/../../escaped";
+        header.as_old_mut().name[..name.len()].copy_from_slice(name);
+        header.set_cksum();
+        builder.append(&header, &b"x"[..]).unwrap();
+
+        builder.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn extract_entry_stays_inside_dest() {
+        let dir = TempDir::new("evil-entry");
+        fs::create_dir(dir.join("outside")).unwrap();
+        fs::create_dir(dir.join("dest")).unwrap();
+        let archive = hostile_tar(&dir);
+
+        assert!(extract_entry(&archive, Path::new("dir"), &dir.join("dest")).is_err());
+        assert!(!dir.join("outside/pwned").exists());
+        assert!(!dir.join("escaped").exists());
+    }
+
+    #[test]
+    fn extract_all_stays_inside_dest() {
+        let dir = TempDir::new("evil-all");
+        fs::create_dir(dir.join("outside")).unwrap();
+        let archive = hostile_tar(&dir);
+
+        assert!(extract_all(&archive, &dir.join("dest"), &mut |_| {}).is_err());
+        assert!(!dir.join("outside/pwned").exists());
+        assert!(!dir.join("escaped").exists());
+    }
+
+    #[test]
+    fn extract_all_unpacks_what_create_packed() {
+        let dir = TempDir::new("archive-roundtrip");
+        fs::create_dir_all(dir.join("src/sub")).unwrap();
+        fs::write(dir.join("src/a.txt"), "a").unwrap();
+        fs::write(dir.join("src/sub/b.txt"), "b").unwrap();
+
+        for name in ["out.tar", "out.tar.gz", "out.tar.xz", "out.zip"] {
+            let archive = dir.join(name);
+            let dest = dir.join(format!("{}.d", name));
+            create(&archive, &[dir.join("src")], &mut |_| {}).unwrap();
+
+            extract_all(&archive, &dest, &mut |_| {}).unwrap();
+            assert_eq!(fs::read_to_string(dest.join("src/a.txt")).unwrap(), "a");
+            assert_eq!(fs::read_to_string(dest.join("src/sub/b.txt")).unwrap(), "b");
+
+            // a second run would overwrite, nothing is written
+            fs::write(dest.join("src/a.txt"), "changed").unwrap();
+            let e = extract_all(&archive, &dest, &mut |_| {}).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+            assert_eq!(fs::read_to_string(dest.join("src/a.txt")).unwrap(), "changed");
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashSet,
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+use super::filemanager;
+
+// write one name per line, let the user edit them in $EDITOR and read them back
+pub fn edit_names(names: &[String]) -> io::Result<Vec<String>> {
+    let (file, mut f) = filemanager::make_temp_file("luru-rename-", ".txt")?;
+    let written = f.write_all((names.join("\n") + "\n").as_bytes());
+    drop(f);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&file);
+        return Err(e);
+    }
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or(String::from("vi"));
+
+    // through sh so EDITOR="code --wait" works too
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&file)
+        .status();
+
+    let r = match status {
+        Ok(s) if s.success() => fs::read_to_string(&file),
+        Ok(s) => Err(io::Error::other(format!("{} exited with {}", editor, s))),
+        Err(e) => Err(e),
+    };
+    let _ = fs::remove_file(&file);
+
+    let content = r?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    Ok(lines)
+}
+
+// pair every path with its new name, only changed names are kept
+pub fn plan(paths: &[PathBuf], new_names: &[String]) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    if paths.len() != new_names.len() {
+        return Err(format!(
+            "expected {} lines but got {}, nothing renamed",
+            paths.len(),
+            new_names.len()
+        ));
+    }
+
+    let sources: HashSet<&PathBuf> = paths.iter().collect();
+    let mut targets = HashSet::new();
+    let mut renames = Vec::new();
+
+    for (from, name) in paths.iter().zip(new_names.iter()) {
+        if name.is_empty() || name.contains('/') || name == "." || name == ".." {
+            return Err(format!("invalid file name: \"{}\"", name));
+        }
+
+        let to = from.with_file_name(name);
+        if !targets.insert(to.clone()) {
+            return Err(format!("{} is used twice", to.display()));
+        }
+        if to == *from {
+            continue;
+        }
+        if to.symlink_metadata().is_ok() && !sources.contains(&to) {
+            return Err(format!("{} already exists", to.display()));
+        }
+
+        renames.push((from.clone(), to));
+    }
+
+    Ok(renames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn plan_keeps_only_changed_names() {
+        let paths = [PathBuf::from("/nowhere/a"), PathBuf::from("/nowhere/b")];
+        let renames = plan(&paths, &names(&["a", "c"])).unwrap();
+        assert_eq!(renames, [(PathBuf::from("/nowhere/b"), PathBuf::from("/nowhere/c"))]);
+    }
+
+    #[test]
+    fn plan_refuses_duplicate_targets() {
+        let paths = [PathBuf::from("/nowhere/a"), PathBuf::from("/nowhere/b")];
+        assert!(plan(&paths, &names(&["c", "c"])).is_err());
+        // b keeping its name is a target too
+        assert!(plan(&paths, &names(&["b", "b"])).is_err());
+    }
+
+    #[test]
+    fn plan_refuses_bad_names_and_line_counts() {
+        let paths = [PathBuf::from("/nowhere/a")];
+        assert!(plan(&paths, &names(&["x/y"])).is_err());
+        assert!(plan(&paths, &names(&[".."])).is_err());
+        assert!(plan(&paths, &names(&[""])).is_err());
+        assert!(plan(&paths, &names(&["b", "c"])).is_err());
+    }
+
+    #[test]
+    fn plan_refuses_existing_files_but_allows_swaps() {
+        let dir = TempDir::new("plan");
+        for name in ["a", "b", "other"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let paths = [dir.join("a"), dir.join("b")];
+
+        assert!(plan(&paths, &names(&["other", "b"])).is_err());
+        assert_eq!(plan(&paths, &names(&["b", "a"])).unwrap().len(), 2);
+    }
+}
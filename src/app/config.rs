@@ -1,4 +1,10 @@
-use super::{filemanager, pathmanager};
+use super::{
+    conflict::ConflictPolicy,
+    filemanager,
+    filter::FilterKind,
+    frecency::Frecency,
+    pathmanager,
+};
 use std::{
     env, fs, io,
     path::{Path, PathBuf},
@@ -7,9 +13,19 @@ use std::{
 pub struct Config {
     pub working_path: PathBuf,
     pub hide_hidden_file: bool,
+    pub follow_links: bool,
+    pub show_real_path: bool,
+    pub conflict_policy: ConflictPolicy,
+    pub sort_by_size: bool,
+    pub filter_kind: FilterKind,
+    pub keep_filter_on_cd: bool,
+    pub grep_gitignore: bool,
+    pub tree_view: bool,
     pub config_file_path: PathBuf,
 
     pub history_path: PathBuf,
+    pub frecency_path: PathBuf,
+    pub frecency: Frecency,
     pub command_history: Vec<String>,
     pub bookmark: Vec<String>,
 }
@@ -29,13 +45,26 @@ impl Config {
         let nav_to_app_command_history = pathmanager::convert_path_to_nav("luru-cmd.log")?;
         let history_path = pathmanager::resolve_path(&config_folder, &nav_to_app_command_history)?;
 
+        let nav_to_app_frecency = pathmanager::convert_path_to_nav("luru-dirs.log")?;
+        let frecency_path = pathmanager::resolve_path(&config_folder, &nav_to_app_frecency)?;
+
         Ok(Config {
             working_path: PathBuf::from("/"),
             command_history: Vec::new(),
             bookmark: Vec::new(),
             hide_hidden_file: true,
+            follow_links: false,
+            show_real_path: false,
+            conflict_policy: ConflictPolicy::Ask,
+            sort_by_size: false,
+            filter_kind: FilterKind::Substring,
+            keep_filter_on_cd: false,
+            grep_gitignore: true,
+            tree_view: false,
             config_file_path: temp_path,
             history_path,
+            frecency_path,
+            frecency: Frecency::new(),
         })
     }
 
@@ -60,6 +89,32 @@ impl Config {
                 "HIDE_HIDDEN_FILE" => {
                     self.hide_hidden_file = vv[1].parse().unwrap_or(false);
                 }
+                "FOLLOW_LINKS" => {
+                    self.follow_links = vv[1].parse().unwrap_or(false);
+                }
+                "SHOW_REAL_PATH" => {
+                    self.show_real_path = vv[1].parse().unwrap_or(false);
+                }
+                "CONFLICT_POLICY" => {
+                    // ask, overwrite, skip, rename or newer
+                    self.conflict_policy = ConflictPolicy::parse(vv[1]).unwrap_or(ConflictPolicy::Ask);
+                }
+                "SORT_BY_SIZE" => {
+                    self.sort_by_size = vv[1].parse().unwrap_or(false);
+                }
+                "FILTER_KIND" => {
+                    // substring, glob or regex
+                    self.filter_kind = FilterKind::parse(vv[1]).unwrap_or(FilterKind::Substring);
+                }
+                "KEEP_FILTER_ON_CD" => {
+                    self.keep_filter_on_cd = vv[1].parse().unwrap_or(false);
+                }
+                "GREP_GITIGNORE" => {
+                    self.grep_gitignore = vv[1].parse().unwrap_or(true);
+                }
+                "TREE_VIEW" => {
+                    self.tree_view = vv[1].parse().unwrap_or(false);
+                }
                 "BOOKMARK" => {
                     let val = vv[1];
                     self.bookmark = val.to_string().split(";").map(|s| s.to_string()).collect();
@@ -69,7 +124,11 @@ impl Config {
         }
 
         // load history
-        self.load_history()
+        self.load_history()?;
+
+        let file = filemanager::read_file(&self.frecency_path)?;
+        self.frecency = Frecency::parse(&file);
+        Ok(())
     }
 
     pub fn load_history(&mut self) -> io::Result<()> {
@@ -87,14 +146,23 @@ impl Config {
 
     pub fn save(&mut self) -> io::Result<()> {
         let content = format!(
-            "WORKING_PATH={}\nHIDE_HIDDEN_FILE={}\nBOOKMARK={}",
+            "WORKING_PATH={}\nHIDE_HIDDEN_FILE={}\nFOLLOW_LINKS={}\nSHOW_REAL_PATH={}\nCONFLICT_POLICY={}\nSORT_BY_SIZE={}\nFILTER_KIND={}\nKEEP_FILTER_ON_CD={}\nGREP_GITIGNORE={}\nTREE_VIEW={}\nBOOKMARK={}",
             self.working_path.display(),
             self.hide_hidden_file.to_string(),
+            self.follow_links,
+            self.show_real_path,
+            self.conflict_policy.name(),
+            self.sort_by_size,
+            self.filter_kind.name(),
+            self.keep_filter_on_cd,
+            self.grep_gitignore,
+            self.tree_view,
             self.bookmark.join(";")
         );
         fs::write(&self.config_file_path, content)?;
 
         fs::write(&self.history_path, self.command_history.join("\n"))?;
+        fs::write(&self.frecency_path, self.frecency.dump())?;
 
         Ok(())
     }
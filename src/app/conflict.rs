@@ -0,0 +1,249 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+
+use super::{filemanager, worker::Transfer};
+
+// what to do when a copy or move target already exists
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    Ask,
+    Overwrite,
+    Skip,
+    Rename,
+    OverwriteIfNewer,
+}
+
+impl ConflictPolicy {
+    // the CONFLICT_POLICY value in the config file
+    pub fn parse(s: &str) -> Option<ConflictPolicy> {
+        match s.trim() {
+            "ask" => Some(ConflictPolicy::Ask),
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            "skip" => Some(ConflictPolicy::Skip),
+            "rename" => Some(ConflictPolicy::Rename),
+            "newer" => Some(ConflictPolicy::OverwriteIfNewer),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Ask => "ask",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::Rename => "rename",
+            ConflictPolicy::OverwriteIfNewer => "newer",
+        }
+    }
+}
+
+// the prompt rows, in order
+const CHOICES: [ConflictPolicy; 4] = [
+    ConflictPolicy::Overwrite,
+    ConflictPolicy::Skip,
+    ConflictPolicy::Rename,
+    ConflictPolicy::OverwriteIfNewer,
+];
+const ROW_APPLY_ALL: usize = CHOICES.len();
+
+// "a.txt" -> "a (1).txt", the first one that is not taken
+pub fn free_name(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut n = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{} ({}){}", stem, n, ext));
+        if candidate.symlink_metadata().is_err() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::symlink_metadata(path).ok()?.modified().ok()
+}
+
+fn is_newer(from: &Path, to: &Path) -> bool {
+    modified(from) > modified(to)
+}
+
+// decide one conflicting pair, None means it is skipped
+pub fn resolve(policy: ConflictPolicy, from: PathBuf, to: PathBuf) -> Option<Transfer> {
+    match policy {
+        ConflictPolicy::Overwrite => Some(Transfer {
+            from,
+            to,
+            replace: true,
+        }),
+        ConflictPolicy::Rename => Some(Transfer {
+            to: free_name(&to),
+            from,
+            replace: false,
+        }),
+        ConflictPolicy::OverwriteIfNewer if is_newer(&from, &to) => Some(Transfer {
+            from,
+            to,
+            replace: true,
+        }),
+        _ => None,
+    }
+}
+
+// asks about every existing target before the job is started
+pub struct ConflictPrompt {
+    pub is_move: bool,
+    pub transfers: Vec<Transfer>,
+    // still undecided, the first one is asked about
+    pub conflicts: Vec<(PathBuf, PathBuf)>,
+    pub skipped: usize,
+    pub apply_all: bool,
+}
+
+impl ConflictPrompt {
+    pub fn current(&self) -> Option<&(PathBuf, PathBuf)> {
+        self.conflicts.first()
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        let Some((from, to)) = self.current() else {
+            return Vec::new();
+        };
+
+        let describe = |path: &Path| {
+            let date = modified(path)
+                .map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            format!(
+                "{}  {}  {}",
+                filemanager::human_size(filemanager::tree_size(path)),
+                date,
+                path.display()
+            )
+        };
+
+        vec![
+            String::from("--> overwrite"),
+            String::from("--> skip"),
+            format!(
+                "--> rename to {}",
+                free_name(to).file_name().unwrap_or_default().to_string_lossy()
+            ),
+            format!(
+                "--> overwrite if newer (source is {})",
+                if is_newer(from, to) { "newer" } else { "not newer" }
+            ),
+            format!(
+                "{} apply to all {} conflict(s)",
+                if self.apply_all { "[x]" } else { "[ ]" },
+                self.conflicts.len()
+            ),
+            String::new(),
+            format!("source  {}", describe(from)),
+            format!("target  {}", describe(to)),
+        ]
+    }
+
+    // pick the row under the cursor, true once every conflict is decided
+    pub fn activate(&mut self, row: usize) -> bool {
+        if row == ROW_APPLY_ALL {
+            self.apply_all = !self.apply_all;
+            return false;
+        }
+        let Some(policy) = CHOICES.get(row) else {
+            return false;
+        };
+
+        let count = if self.apply_all {
+            self.conflicts.len()
+        } else {
+            1
+        };
+        for (from, to) in self.conflicts.drain(..count) {
+            match resolve(*policy, from, to) {
+                Some(transfer) => self.transfers.push(transfer),
+                None => self.skipped += 1,
+            }
+        }
+        self.conflicts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+    use std::time::Duration;
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        fs::File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    #[test]
+    fn free_name_skips_taken_names() {
+        let dir = TempDir::new("conflict-free");
+        fs::write(dir.join("a.txt"), "").unwrap();
+        fs::write(dir.join("a (1).txt"), "").unwrap();
+        fs::write(dir.join("noext"), "").unwrap();
+
+        assert_eq!(free_name(&dir.join("a.txt")), dir.join("a (2).txt"));
+        assert_eq!(free_name(&dir.join("noext")), dir.join("noext (1)"));
+    }
+
+    #[test]
+    fn resolve_follows_the_policy() {
+        let dir = TempDir::new("conflict-resolve");
+        let (old, new) = (dir.join("old"), dir.join("new"));
+        fs::write(&old, "").unwrap();
+        fs::write(&new, "").unwrap();
+        let now = SystemTime::now();
+        set_modified(&old, now - Duration::from_secs(3600));
+        set_modified(&new, now);
+
+        let overwrite = resolve(ConflictPolicy::Overwrite, old.clone(), new.clone()).unwrap();
+        assert!(overwrite.replace && overwrite.to == new);
+        assert!(resolve(ConflictPolicy::Skip, old.clone(), new.clone()).is_none());
+        let rename = resolve(ConflictPolicy::Rename, old.clone(), new.clone()).unwrap();
+        assert!(!rename.replace && rename.to == dir.join("new (1)"));
+
+        // only a newer source replaces the target
+        assert!(resolve(ConflictPolicy::OverwriteIfNewer, old.clone(), new.clone()).is_none());
+        let newer = resolve(ConflictPolicy::OverwriteIfNewer, new.clone(), old.clone()).unwrap();
+        assert!(newer.replace && newer.to == old);
+    }
+
+    #[test]
+    fn prompt_decides_one_or_all() {
+        let dir = TempDir::new("conflict-prompt");
+        let conflicts = (0..3)
+            .map(|i| (dir.join(format!("from{}", i)), dir.join(format!("to{}", i))))
+            .collect();
+        let mut prompt = ConflictPrompt {
+            is_move: false,
+            transfers: Vec::new(),
+            conflicts,
+            skipped: 0,
+            apply_all: false,
+        };
+
+        // skip the first one only
+        assert!(!prompt.activate(1));
+        assert_eq!((prompt.skipped, prompt.conflicts.len()), (1, 2));
+
+        // overwrite the rest at once
+        assert!(!prompt.activate(ROW_APPLY_ALL));
+        assert!(prompt.activate(0));
+        assert_eq!(prompt.transfers.len(), 2);
+        assert!(prompt.transfers.iter().all(|t| t.replace));
+        assert!(prompt.current().is_none());
+    }
+}
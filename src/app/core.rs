@@ -1,11 +1,29 @@
-use std::{env, io::{self, Write}, path::PathBuf};
+use std::{cmp::Reverse, collections::HashSet, env, fs, io::{self, Write}, path::{Path, PathBuf}, time::Duration};
 
 use super::{
+    archive, bulkrename,
+    checksum::{self, Algorithm},
     config::Config,
+    conflict::{self, ConflictPolicy, ConflictPrompt},
+    diff::{self, DiffKind, DiffRow},
+    dirsize::DirSizes,
+    diskusage::{self, DuNode},
+    dupes::{self, DupeGroup},
     filemanager::{self, ReadDirItems},
-    keyboard, pathmanager,
-    termin::Termin,
-    ui::UI,
+    filter::Filter,
+    find::{FindOptions, Search},
+    fuzzy::Finder,
+    grep::{Grep, GrepOptions},
+    journal::Journal,
+    keyboard,
+    navhistory::NavHistory,
+    pathmanager,
+    permissions::{self, PermDialog},
+    termin::{self, Termin},
+    trash::{self, TrashItem},
+    tree::{TreeRow, TreeState},
+    ui::{RowStyle, UI},
+    worker::{Job, JobReport, Scan, Scanned, Transfer},
 };
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
 use ctrlc;
@@ -15,6 +33,19 @@ enum AppMode {
     CommandHistory,
     TerminalOnly,
     Bookmark,
+    Trash,
+    BulkRename,
+    Permissions,
+    Conflict,
+    Dupes,
+    DiskUsage,
+    Diff,
+    Checksums,
+    Jump,
+    Fuzzy,
+    Find,
+    Grep,
+    Preview,
 }
 
 pub struct App {
@@ -24,11 +55,59 @@ pub struct App {
     app_ui: UI,
     app_term: Termin,
     decs_label: String,
+    status_label: String,
     command: String,
     sugest: String,
     content: Vec<ReadDirItems>,
     content_to_read: Vec<String>,
     command_history: Vec<String>,
+    marked: HashSet<PathBuf>,
+    clipboard: Vec<PathBuf>,
+    clipboard_cut: bool,
+    trash_items: Vec<TrashItem>,
+    // trash entry waiting for y before it is deleted forever
+    purge_confirm: Option<TrashItem>,
+    journal: Journal,
+    pending_renames: Vec<(PathBuf, PathBuf)>,
+    perm_dialog: Option<PermDialog>,
+    job: Option<Job>,
+    // a :du or :dupes walk on a worker thread
+    scan: Option<Scan>,
+    conflict_prompt: Option<ConflictPrompt>,
+    dupe_groups: Vec<DupeGroup>,
+    // the file on every row of the dupes view, None for group headers
+    dupe_rows: Vec<Option<PathBuf>>,
+    // put the cursor on this entry after the next scan
+    focus_path: Option<PathBuf>,
+    dir_sizes: DirSizes,
+    archive_listings: archive::Listings,
+    // journal.changes() the cached sizes belong to
+    sizes_changes: usize,
+    // show the size of this folder once it is known
+    size_status_for: Option<PathBuf>,
+    du_root: Option<DuNode>,
+    // child indexes from du_root down to the folder on screen
+    du_stack: Vec<usize>,
+    diff_rows: Vec<DiffRow>,
+    checksum_rows: Vec<(String, Option<&'static str>)>,
+    checksum_title: String,
+    nav_history: NavHistory,
+    // :z matches that scored the same
+    jump_choices: Vec<PathBuf>,
+    finder: Option<Finder>,
+    filter: Filter,
+    // keys go to the filter instead of the command line
+    filter_editing: bool,
+    // entries in the folder before the filter, "../" not counted
+    filter_total: usize,
+    search: Option<Search>,
+    grep: Option<Grep>,
+    // the grep hit on screen, its file is shown numbered in preview_rows
+    preview_hit: usize,
+    preview_rows: Vec<String>,
+    tree: TreeState,
+    // one per entry of content while the tree view is on
+    tree_rows: Vec<TreeRow>,
 
     config: Config,
 
@@ -70,10 +149,46 @@ impl App {
             app_term: Termin::new(),
             app_mode: AppMode::Normal,
             decs_label,
+            status_label: String::new(),
             command: String::new(),
             content: Vec::<ReadDirItems>::new(),
             command_history: config.command_history.clone(),
             content_to_read: Vec::<String>::new(),
+            marked: HashSet::new(),
+            clipboard: Vec::new(),
+            clipboard_cut: false,
+            trash_items: Vec::new(),
+            purge_confirm: None,
+            journal: Journal::new(),
+            pending_renames: Vec::new(),
+            perm_dialog: None,
+            job: None,
+            scan: None,
+            conflict_prompt: None,
+            dupe_groups: Vec::new(),
+            dupe_rows: Vec::new(),
+            focus_path: None,
+            dir_sizes: DirSizes::new(),
+            archive_listings: archive::Listings::new(),
+            sizes_changes: 0,
+            size_status_for: None,
+            du_root: None,
+            du_stack: Vec::new(),
+            diff_rows: Vec::new(),
+            checksum_rows: Vec::new(),
+            checksum_title: String::new(),
+            nav_history: NavHistory::new(),
+            jump_choices: Vec::new(),
+            finder: None,
+            filter: Filter::new(config.filter_kind),
+            filter_editing: false,
+            filter_total: 0,
+            search: None,
+            grep: None,
+            preview_hit: 0,
+            preview_rows: Vec::new(),
+            tree: TreeState::new(),
+            tree_rows: Vec::new(),
             config,
             x_cursor: 0,
             re_read: true,
@@ -89,7 +204,12 @@ impl App {
         let mut dirs: Vec<ReadDirItems> = Vec::new();
         let mut files: Vec<ReadDirItems> = Vec::new();
 
-        let r = filemanager::read_dir(&self.current_path, &self.config.hide_hidden_file);
+        let r = filemanager::read_dir(
+            &self.current_path,
+            &self.config.hide_hidden_file,
+            &mut self.archive_listings,
+        );
+        let listed = r.is_ok();
 
         if let Err(e) = r {
             self.decs_label = format!("\x1b[97m\x1b[41mgot an error! kind of:{}\x1b[0m", e.kind());
@@ -99,12 +219,16 @@ impl App {
                 navigation_type: pathmanager::NavigationCommand::Absolute(self.temp_path.clone()),
                 file_name: String::from("/"),
                 label: String::from(self.temp_path.to_string_lossy()),
+                is_dir: true,
+                link_target: None,
+                is_broken_link: false,
             });
         } else if let Ok(o) = r {
             for v in o {
-                if v.path.is_dir() {
+                if v.is_dir {
                     dirs.push(v);
-                } else if v.path.is_file() {
+                } else if v.path.is_file() || v.is_broken_link || !v.path.exists() {
+                    // entries inside an archive don't exist on disk
                     files.push(v);
                 }
             }
@@ -115,12 +239,12 @@ impl App {
                     navigation_type: pathmanager::convert_path_to_nav("../")?,
                     file_name: String::from("../"),
                     label: String::from("../"),
+                    is_dir: true,
+                    link_target: None,
+                    is_broken_link: false,
                 });
             }
 
-            dirs.sort_by(|a, b| a.label.cmp(&b.label.clone()));
-            files.sort_by(|a, b| a.label.cmp(&b.label.clone()));
-
             res.append(&mut dirs);
             res.append(&mut files);
 
@@ -131,27 +255,90 @@ impl App {
         }
 
         self.content = res;
-        self.content_to_read = self.content.iter().map(|f| f.label.clone()).collect();
+
+        // sizes may be stale once luru changed something
+        if self.journal.changes() != self.sizes_changes {
+            self.sizes_changes = self.journal.changes();
+            self.dir_sizes.clear();
+        }
+        self.dir_sizes.show(&self.current_path);
+        if listed && archive::split_path(&self.current_path).is_none() {
+            for item in self.content.iter().filter(|i| i.is_dir && i.file_name != "../") {
+                self.dir_sizes.request(&item.path);
+            }
+        }
+
+        self.sort_content();
+        self.filter_total = self.content.iter().filter(|i| i.file_name != "../").count();
+        if self.filter.is_active() {
+            let filter = &self.filter;
+            self.content
+                .retain(|i| i.file_name == "../" || filter.matches(&i.file_name));
+        }
+
+        self.tree_rows.clear();
+        if self.config.tree_view && listed {
+            let items = std::mem::take(&mut self.content);
+            let hide_hidden_file = self.config.hide_hidden_file;
+            let by_size = self.config.sort_by_size;
+            let sizes = &self.dir_sizes;
+            let listings = &mut self.archive_listings;
+            let mut read = |path: &Path| {
+                let mut children =
+                    filemanager::read_dir(&path.to_path_buf(), &hide_hidden_file, listings)
+                        .unwrap_or_default();
+                sort_items(&mut children, by_size, sizes);
+                children
+            };
+            (self.content, self.tree_rows) = self.tree.flatten(&self.current_path, items, &mut read);
+        }
+        self.content_to_read = self.listing_labels();
+        self.marked.retain(|p| p.symlink_metadata().is_ok());
 
         Ok(())
     }
 
     pub fn open_dir(&mut self) -> io::Result<()> {
+        // a filter can leave nothing to open
+        let Some(path_selected) = self.content.get(self.app_ui.content_cursor) else {
+            return Ok(());
+        };
+        if path_selected.is_dir && self.is_ignore_exec && path_selected.file_name != "../" {
+            // fs::metadata().len() of a folder is only its own entry
+            self.size_status_for = Some(path_selected.path.clone());
+            self.set_status(&format!("{}: computing size...", path_selected.file_name));
+            self.show_dir_size();
+        }
         let path_selected = &self.content[self.app_ui.content_cursor];
-        if path_selected.path.is_dir() {
-            let new_path_nav = &path_selected.navigation_type;
-            self.current_path = pathmanager::resolve_path(&self.current_path, &new_path_nav)?;
+        if path_selected.is_dir {
+            if path_selected.link_target.is_some() && self.config.follow_links {
+                self.current_path = fs::canonicalize(&path_selected.path)?;
+            } else {
+                let new_path_nav = &path_selected.navigation_type;
+                self.current_path = pathmanager::resolve_path(&self.current_path, new_path_nav)?;
+            }
 
             self.re_read = true;
         }
-        if path_selected.path.is_file() {
+        if path_selected.path.is_file() && archive::kind_of(&path_selected.path).is_some() {
+            // browse the archive like a folder
+            self.current_path = path_selected.path.clone();
+            self.re_read = true;
+        } else if path_selected.path.is_file() {
             let meta = filemanager::metadata(&path_selected.path)?;
             // set desc to file information
-            self.decs_label = format!(
+            self.status_label = format!(
                 "\x1b[34mfile: {} size: {} modified: {}\x1b[0m",
                 meta.display_name, meta.size, meta.modified
             );
         }
+        if path_selected.is_broken_link {
+            let target = path_selected.link_target.clone().unwrap_or_default();
+            self.set_error(&format!("broken link, {} does not exist", target.display()));
+        } else if !path_selected.is_dir && !path_selected.path.exists() {
+            // an entry inside an archive
+            self.set_status(":extract copies this entry out of the archive");
+        }
 
         Ok(())
     }
@@ -171,19 +358,32 @@ impl App {
 
                 match self.app_mode {
                     AppMode::Normal => {
+                        if is_cd && !self.config.keep_filter_on_cd {
+                            self.filter.clear();
+                            self.filter_editing = false;
+                        }
                         self.do_a_scan()?;
                         if is_cd {
+                            self.nav_history.arrive(&self.current_path);
+                            if self.current_path.is_dir() {
+                                self.config.frecency.visit(&self.current_path);
+                            }
+                            self.marked.clear();
                             if self.content.len() > 2 {
                                 self.app_ui.content_cursor = 1;
                             } else {
                                 self.app_ui.content_cursor = 0;
                             }
                             self.app_ui.content_render_from = 0;
-                        } else {
+                        }
+                        if let Some(path) = self.focus_path.take() {
+                            if let Some(i) = self.content.iter().position(|i| i.path == path) {
+                                self.app_ui.content_cursor = i;
+                            }
                         }
 
-                        self.content_to_read =
-                            self.content.iter().map(|f| f.label.clone()).collect();
+                        self.content_to_read = self.listing_labels();
+                        self.clamp_cursor();
 
                         // let path_label = self.current_path.to_string_lossy().into_owned();
                         // if self.config.bookmark.contains(&path_label) {
@@ -201,6 +401,114 @@ impl App {
                         self.app_ui.content_render_from = 0;
                         self.app_ui.content_cursor = 0;
                     }
+                    AppMode::BulkRename => {
+                        self.content_to_read = self
+                            .pending_renames
+                            .iter()
+                            .map(|(from, to)| {
+                                format!(
+                                    "{} -> {}",
+                                    from.file_name().unwrap_or_default().to_string_lossy(),
+                                    to.file_name().unwrap_or_default().to_string_lossy()
+                                )
+                            })
+                            .collect();
+                        self.app_ui.content_render_from = 0;
+                        self.app_ui.content_cursor = 0;
+                    }
+                    AppMode::Permissions => {
+                        self.content_to_read = self
+                            .perm_dialog
+                            .as_ref()
+                            .map(|d| d.rows())
+                            .unwrap_or_default();
+                        self.clamp_cursor();
+                    }
+                    AppMode::Dupes => {
+                        self.dupe_rows.clear();
+                        self.content_to_read.clear();
+                        for group in self.dupe_groups.iter() {
+                            self.dupe_rows.push(None);
+                            self.content_to_read.push(format!(
+                                "{} copies of {}",
+                                group.paths.len(),
+                                filemanager::human_size(group.size)
+                            ));
+                            for path in group.paths.iter() {
+                                let rel = path.strip_prefix(&self.current_path).unwrap_or(path);
+                                self.dupe_rows.push(Some(path.clone()));
+                                self.content_to_read.push(format!("    {}", rel.display()));
+                            }
+                        }
+                        self.clamp_cursor();
+                    }
+                    AppMode::DiskUsage => {
+                        let mut rows = Vec::new();
+                        if let Some(node) = self.du_node() {
+                            if !self.du_stack.is_empty() {
+                                rows.push(String::from("../"));
+                            }
+                            rows.extend(node.children.iter().map(|c| c.row(node.size)));
+                        }
+                        self.content_to_read = rows;
+                        self.clamp_cursor();
+                    }
+                    AppMode::Jump => {
+                        self.content_to_read =
+                            self.jump_choices.iter().map(|p| p.display().to_string()).collect();
+                        self.app_ui.content_render_from = 0;
+                        self.app_ui.content_cursor = 0;
+                    }
+                    AppMode::Fuzzy => {
+                        self.content_to_read = self.finder_labels();
+                        self.clamp_cursor();
+                    }
+                    AppMode::Find => {
+                        if let Some(search) = self.search.as_mut() {
+                            // gone after a delete, rename or move
+                            search.results.retain(|i| i.path.symlink_metadata().is_ok());
+                        }
+                        self.marked.retain(|p| p.symlink_metadata().is_ok());
+                        self.content_to_read =
+                            self.search.as_ref().map(|s| s.labels()).unwrap_or_default();
+                        self.clamp_cursor();
+                    }
+                    AppMode::Grep => {
+                        self.content_to_read = self.grep_labels();
+                        self.clamp_cursor();
+                    }
+                    AppMode::Preview => {
+                        self.content_to_read = self.preview_rows.clone();
+                    }
+                    AppMode::Checksums => {
+                        self.content_to_read = self.checksum_rows.iter().map(|(t, _)| t.clone()).collect();
+                    }
+                    AppMode::Diff => {
+                        self.content_to_read = self.diff_rows.iter().map(|(t, _)| t.clone()).collect();
+                    }
+                    AppMode::Conflict => {
+                        self.content_to_read = self
+                            .conflict_prompt
+                            .as_ref()
+                            .map(|p| p.rows())
+                            .unwrap_or_default();
+                    }
+                    AppMode::Trash => {
+                        match trash::list() {
+                            Ok((items, 0)) => self.trash_items = items,
+                            Ok((items, skipped)) => {
+                                self.trash_items = items;
+                                self.set_error(&format!("{} unreadable .trashinfo file(s) left out", skipped));
+                            }
+                            Err(e) => self.set_error(&format!("cannot read trash: {}", e)),
+                        }
+                        self.content_to_read = self
+                            .trash_items
+                            .iter()
+                            .map(|t| format!("{}  {}", t.deletion_date, t.original_path.display()))
+                            .collect();
+                        self.clamp_cursor();
+                    }
                     _ => {}
                 }
                 self.app_ui.clear_screen()?;
@@ -211,9 +519,23 @@ impl App {
 
             self.display_ui()?;
 
-            // wait until event
-            let event: event::Event = keyboard::wait_for_keyboard()?;
-            self.event_handler(event)?;
+            // wait until event, background work wakes the loop up to draw its progress
+            let is_walking = self.finder.as_ref().is_some_and(|f| f.is_walking())
+                || self.search.as_ref().is_some_and(|s| s.is_running())
+                || self.grep.as_ref().is_some_and(|g| g.is_running());
+            if self.job.is_none() && self.scan.is_none() && !self.dir_sizes.is_busy() && !is_walking {
+                let event: event::Event = keyboard::wait_for_keyboard()?;
+                self.event_handler(event)?;
+            } else if let Some(event) = keyboard::poll_keyboard(Duration::from_millis(100))? {
+                self.event_handler(event)?;
+            } else {
+                self.poll_job();
+                self.poll_scan();
+                self.poll_dir_sizes();
+                self.poll_finder();
+                self.poll_search();
+                self.poll_grep();
+            }
         }
 
         Ok(())
@@ -301,28 +623,348 @@ impl App {
                 _ => 
                 self.decs_label =  format!("\x1b[1m\x1b[035m{}\x1b[0m exit \x1b[1m\x1b[035m{}\x1b[0m terminal only \x1b[1m\x1b[035m{}\x1b[0m command historys", "^c", "^t", "^h")
             }
+
+            if !self.marked.is_empty() {
+                self.decs_label = format!("\x1b[33m{} marked\x1b[0m {}", self.marked.len(), self.decs_label);
+            }
+            if !self.clipboard.is_empty() {
+                self.decs_label = format!("\x1b[36m{} in clipboard\x1b[0m {}", self.clipboard.len(), self.decs_label);
+            }
+            if self.filter_editing {
+                self.decs_label = format!(
+                    "{} \x1b[1m\x1b[035m{}\x1b[0m {} \x1b[1m\x1b[035m{}\x1b[0m keep \x1b[1m\x1b[035m{}\x1b[0m clear",
+                    self.filter_label(), "Tab", self.filter.kind.next().name(), "Enter", "Esc",
+                );
+            } else if self.filter.is_active() {
+                self.decs_label = format!("{} {}", self.filter_label(), self.decs_label);
+            }
         
         } else if self.app_mode == AppMode::Bookmark || self.app_mode == AppMode::CommandHistory {
             self.decs_label = format!(
                 "\x1b[1m\x1b[035m{}\x1b[0m exit \x1b[1m\x1b[035m{}\x1b[0m back",
                 "^c", "Esc",
             );
+        } else if self.app_mode == AppMode::BulkRename {
+            self.decs_label = format!(
+                "rename {} item(s)? \x1b[1m\x1b[035m{}\x1b[0m apply \x1b[1m\x1b[035m{}\x1b[0m cancel",
+                self.pending_renames.len(), "Enter", "Esc",
+            );
+        } else if let (AppMode::Permissions, Some(dialog)) = (&self.app_mode, &self.perm_dialog) {
+            self.decs_label = format!(
+                "{} \x1b[1m\x1b[035m{}\x1b[0m toggle \x1b[1m\x1b[035m{}\x1b[0m back",
+                dialog.path.display(), "Enter", "Esc",
+            );
+        } else if let (AppMode::Conflict, Some((_, to))) =
+            (&self.app_mode, self.conflict_prompt.as_ref().and_then(|p| p.current()))
+        {
+            self.decs_label = format!(
+                "\x1b[33m{} already exists\x1b[0m \x1b[1m\x1b[035m{}\x1b[0m choose \x1b[1m\x1b[035m{}\x1b[0m cancel",
+                to.display(), "Enter", "Esc",
+            );
+        } else if self.app_mode == AppMode::Dupes {
+            let wasted: u64 = self.dupe_groups.iter().map(|g| g.wasted()).sum();
+            self.decs_label = format!(
+                "{} wasted \x1b[1m\x1b[035m{}\x1b[0m mark \x1b[1m\x1b[035m{}\x1b[0m mark all but first \x1b[1m\x1b[035m{}\x1b[0m trash \x1b[1m\x1b[035m{}\x1b[0m open \x1b[1m\x1b[035m{}\x1b[0m back",
+                filemanager::human_size(wasted), "Space", ":sa", "Del", "Enter", "Esc",
+            );
+            if !self.marked.is_empty() {
+                self.decs_label = format!("\x1b[33m{} marked\x1b[0m {}", self.marked.len(), self.decs_label);
+            }
+        } else if let (AppMode::DiskUsage, Some(node)) = (&self.app_mode, self.du_node()) {
+            self.decs_label = format!(
+                "{} total \x1b[1m\x1b[035m{}\x1b[0m open \x1b[1m\x1b[035m{}\x1b[0m trash \x1b[1m\x1b[035m{}\x1b[0m back",
+                filemanager::human_size(node.size), "Enter", "Del", "Esc",
+            );
+        } else if self.app_mode == AppMode::Jump {
+            self.decs_label = format!(
+                "{} folders match equally well \x1b[1m\x1b[035m{}\x1b[0m go \x1b[1m\x1b[035m{}\x1b[0m back",
+                self.jump_choices.len(), "Enter", "Esc",
+            );
+        } else if let (AppMode::Fuzzy, Some(finder)) = (&self.app_mode, &self.finder) {
+            self.decs_label = format!(
+                "{} of {}{} \x1b[1m\x1b[035m{}\x1b[0m jump \x1b[1m\x1b[035m{}\x1b[0m back",
+                finder.matched(),
+                finder.total(),
+                if finder.is_walking() { " searching..." } else { "" },
+                "Enter", "Esc",
+            );
+        } else if let (AppMode::Find, Some(search)) = (&self.app_mode, &self.search) {
+            let state = if search.is_running() {
+                " searching..."
+            } else if search.cancelled {
+                " stopped"
+            } else {
+                ""
+            };
+            self.decs_label = format!(
+                "{} found for {} in {} scanned{} \x1b[1m\x1b[035m{}\x1b[0m open \x1b[1m\x1b[035m{}\x1b[0m folder \x1b[1m\x1b[035m{}\x1b[0m mark \x1b[1m\x1b[035m{}\x1b[0m {}",
+                search.results.len(), search.pattern, search.scanned, state,
+                "Enter", ":goto", "Space", "Esc",
+                if search.is_running() { "stop" } else { "back" },
+            );
+            if !self.marked.is_empty() {
+                self.decs_label = format!("\x1b[33m{} marked\x1b[0m {}", self.marked.len(), self.decs_label);
+            }
+        } else if let (AppMode::Grep, Some(grep)) = (&self.app_mode, &self.grep) {
+            let state = if grep.truncated {
+                " first hits only"
+            } else if grep.is_running() {
+                " searching..."
+            } else if grep.cancelled {
+                " stopped"
+            } else {
+                ""
+            };
+            self.decs_label = format!(
+                "{} hits for {} in {} files{} \x1b[1m\x1b[035m{}\x1b[0m preview \x1b[1m\x1b[035m{}\x1b[0m editor \x1b[1m\x1b[035m{}\x1b[0m {}",
+                grep.hits.len(), grep.pattern, grep.files_searched(), state,
+                "Enter", ":edit", "Esc",
+                if grep.is_running() { "stop" } else { "back" },
+            );
+        } else if let (AppMode::Preview, Some(hit)) =
+            (&self.app_mode, self.grep.as_ref().and_then(|g| g.hits.get(self.preview_hit)))
+        {
+            self.decs_label = format!(
+                "{}:{} \x1b[1m\x1b[035m{}\x1b[0m editor at this line \x1b[1m\x1b[035m{}\x1b[0m back to hits",
+                hit.path.display(), hit.line, "Enter", "Esc",
+            );
+        } else if self.app_mode == AppMode::Checksums {
+            self.decs_label = format!(
+                "{} \x1b[1m\x1b[035m{}\x1b[0m scroll \x1b[1m\x1b[035m{}\x1b[0m back",
+                self.checksum_title, "Up/Down", "Esc",
+            );
+        } else if self.app_mode == AppMode::Diff {
+            let added = self.diff_rows.iter().filter(|(_, k)| *k == DiffKind::Added).count();
+            let removed = self.diff_rows.iter().filter(|(_, k)| *k == DiffKind::Removed).count();
+            self.decs_label = format!(
+                "\x1b[32m+{}\x1b[0m \x1b[31m-{}\x1b[0m \x1b[1m\x1b[035m{}\x1b[0m scroll \x1b[1m\x1b[035m{}\x1b[0m back",
+                added, removed, "Up/Down", "Esc",
+            );
+        } else if self.app_mode == AppMode::Trash {
+            self.decs_label = format!(
+                "\x1b[1m\x1b[035m{}\x1b[0m back \x1b[1m\x1b[035m{}\x1b[0m restore \x1b[1m\x1b[035m{}\x1b[0m delete forever",
+                "Esc", "Enter", "Del",
+            );
         }
 
         if self.is_ignore_exec {
             self.decs_label = format!( "ignoring enter to exec, press \x1b[1m\x1b[035m{}\x1b[0m again to disable",
             "insert",);
         }
+
+        if let Some(job) = &self.job {
+            self.decs_label = job.status_line();
+        }
+        if let Some(scan) = &self.scan {
+            self.decs_label = scan.status_line();
+        }
+
+        if !self.status_label.is_empty() {
+            self.decs_label = self.status_label.clone();
+        }
+    }
+
+    // see sort_items, "../" stays on top
+    fn sort_content(&mut self) {
+        let start = self.content.iter().take_while(|i| i.file_name == "../").count();
+        sort_items(&mut self.content[start..], self.config.sort_by_size, &self.dir_sizes);
+    }
+
+    fn listing_labels(&self) -> Vec<String> {
+        self.content
+            .iter()
+            .map(|i| match self.dir_sizes.get(&i.path) {
+                Some(size) if i.is_dir && i.file_name != "../" => {
+                    format!("{}  {}", i.label, filemanager::human_size(size))
+                }
+                _ => i.label.clone(),
+            })
+            .collect()
+    }
+
+    // fill in sizes that finished since the last redraw
+    fn poll_dir_sizes(&mut self) {
+        let done = self.dir_sizes.poll();
+        if done.is_empty() {
+            return;
+        }
+
+        if self.app_mode == AppMode::Normal {
+            let selected = self.content.get(self.app_ui.content_cursor).map(|i| i.path.clone());
+            // the tree is sorted again on the next scan
+            if self.config.sort_by_size && self.tree_rows.is_empty() {
+                self.sort_content();
+            }
+            self.content_to_read = self.listing_labels();
+            if let Some(i) = selected.and_then(|p| self.content.iter().position(|i| i.path == p)) {
+                self.app_ui.content_cursor = i;
+            }
+        }
+        if self.size_status_for.as_ref().is_some_and(|p| done.contains(p)) {
+            self.show_dir_size();
+        }
+    }
+
+    fn show_dir_size(&mut self) {
+        let Some(path) = self.size_status_for.clone() else {
+            return;
+        };
+        match self.dir_sizes.get(&path) {
+            Some(size) => {
+                self.size_status_for = None;
+                self.set_status(&format!(
+                    "folder: {} total size: {} ({} bytes)",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    filemanager::human_size(size),
+                    size
+                ));
+            }
+            None if self.dir_sizes.request(&path) => {}
+            None => {
+                self.size_status_for = None;
+                self.set_status(&format!(
+                    "{} is a link or another filesystem, its size is not computed",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ));
+            }
+        }
+    }
+
+    fn set_status(&mut self, msg: &str) {
+        self.status_label = format!("\x1b[34m{}\x1b[0m", msg);
+    }
+
+    // redraw the status line right away, used while a slow operation runs
+    fn show_progress(&mut self, msg: &str) {
+        self.set_status(msg);
+        let _ = self
+            .app_ui
+            .set_frame_content(self.current_path.clone(), self.status_label.clone());
+    }
+
+    // keep the cursor on a row after content_to_read shrank
+    fn clamp_cursor(&mut self) {
+        if self.app_ui.content_cursor >= self.content_to_read.len() {
+            self.app_ui.content_cursor = self.content_to_read.len().saturating_sub(1);
+        }
+    }
+
+    fn set_error(&mut self, msg: &str) {
+        self.status_label = format!("\x1b[97m\x1b[41m{}\x1b[0m", msg);
+    }
+
+    // the entries file operations work on, none in views whose rows
+    // are not files of the listing
+    fn listing(&self) -> &[ReadDirItems] {
+        match (&self.app_mode, &self.search) {
+            (AppMode::Find, Some(search)) => &search.results,
+            (AppMode::Normal | AppMode::TerminalOnly, _) => &self.content,
+            _ => &[],
+        }
+    }
+
+    // entry under the cursor, the "../" entry is never a target
+    fn selected_item(&self) -> Option<&ReadDirItems> {
+        self.listing()
+            .get(self.app_ui.content_cursor)
+            .filter(|item| item.file_name != "../")
     }
 
     fn display_ui(&mut self) -> io::Result<()> {
         // self.decs_label = format!("type 'exit' to exit");
         self.decide_decs_label();
 
+        let path_label = if let (AppMode::DiskUsage, Some(node)) = (&self.app_mode, self.du_node()) {
+            node.path.clone()
+        } else if self.config.show_real_path {
+            fs::canonicalize(&self.current_path).unwrap_or(self.current_path.clone())
+        } else {
+            self.current_path.clone()
+        };
         self.app_ui
-            .set_frame_content(self.current_path.clone(), self.decs_label.clone())?;
-
-        self.app_ui.render_content(&self.content_to_read)?;
+            .set_frame_content(path_label, self.decs_label.clone())?;
+
+        let row_styles: Vec<RowStyle> = if matches!(self.app_mode, AppMode::Normal | AppMode::Find) {
+            let guides = if self.app_mode == AppMode::Normal { self.tree_rows.as_slice() } else { &[] };
+            self.listing()
+                .iter()
+                .enumerate()
+                .map(|(n, i)| RowStyle {
+                    marked: self.marked.contains(&i.path),
+                    color: if i.is_broken_link {
+                        Some("\x1b[31m\x1b[9m")
+                    } else if i.link_target.is_some() {
+                        Some("\x1b[2m\x1b[36m")
+                    } else {
+                        None
+                    },
+                    prefix: guides.get(n).map(|r| r.guide.clone()).unwrap_or_default(),
+                    ..Default::default()
+                })
+                .collect()
+        } else if self.app_mode == AppMode::Checksums {
+            self.checksum_rows
+                .iter()
+                .map(|(_, color)| RowStyle {
+                    color: *color,
+                    ..Default::default()
+                })
+                .collect()
+        } else if self.app_mode == AppMode::Diff {
+            self.diff_rows
+                .iter()
+                .map(|(_, kind)| RowStyle {
+                    marked: false,
+                    color: match kind {
+                        DiffKind::Header => Some("\x1b[1m"),
+                        DiffKind::Hunk => Some("\x1b[36m"),
+                        DiffKind::Added => Some("\x1b[32m"),
+                        DiffKind::Removed => Some("\x1b[31m"),
+                        DiffKind::Context => None,
+                    },
+                    ..Default::default()
+                })
+                .collect()
+        } else if let (AppMode::Grep, Some(grep)) = (&self.app_mode, &self.grep) {
+            grep.rows()
+                .into_iter()
+                .map(|(_, positions)| RowStyle {
+                    highlight: positions,
+                    ..Default::default()
+                })
+                .collect()
+        } else if let (AppMode::Preview, Some(hit)) =
+            (&self.app_mode, self.grep.as_ref().and_then(|g| g.hits.get(self.preview_hit)))
+        {
+            (0..self.preview_rows.len())
+                .map(|i| RowStyle {
+                    color: (i + 1 == hit.line).then_some("\x1b[33m"),
+                    ..Default::default()
+                })
+                .collect()
+        } else if let (AppMode::Fuzzy, Some(finder)) = (&self.app_mode, &self.finder) {
+            finder
+                .rows()
+                .into_iter()
+                .map(|(_, positions)| RowStyle {
+                    highlight: positions,
+                    ..Default::default()
+                })
+                .collect()
+        } else if self.app_mode == AppMode::Dupes {
+            self.dupe_rows
+                .iter()
+                .map(|row| RowStyle {
+                    marked: row.as_ref().is_some_and(|p| self.marked.contains(p)),
+                    color: if row.is_none() { Some("\x1b[33m") } else { None },
+                    ..Default::default()
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.app_ui
+            .render_content(&self.content_to_read, &row_styles)?;
 
         // self.move_cursor(0, self.window_size.1.wrapping_sub(1));
         self.app_ui.move_cursor(2, self.app_ui.window_size.1)?;
@@ -413,6 +1055,21 @@ impl App {
         Ok(())
     }
     fn key_event_handler(&mut self, key_event: KeyEvent) -> io::Result<()> {
+        self.status_label.clear();
+
+        if let Some(item) = self.purge_confirm.take() {
+            if key_event.code == KeyCode::Char('y') {
+                self.purge_trash(&item);
+            } else {
+                self.set_status("nothing was deleted");
+            }
+            return Ok(());
+        }
+
+        if self.filter_editing && self.app_mode == AppMode::Normal && self.filter_key(key_event) {
+            return Ok(());
+        }
+
         match key_event.code {
             KeyCode::Char(ch) => {
                 let is_control_pressed: bool = key_event.modifiers.contains(KeyModifiers::CONTROL);
@@ -423,10 +1080,25 @@ impl App {
                         'f' => self.app_mode = AppMode::Normal,
                         't' => self.app_mode = AppMode::TerminalOnly,
                         'b' => self.app_mode = AppMode::Bookmark,
+                        'x' => self.cancel_job(),
+                        'p' => self.open_finder(),
+                        's' => {
+                            self.app_mode = AppMode::Normal;
+                            self.filter_editing = true;
+                        }
                         _ => {}
                     }
                     self.re_read = true;
                     // self.app_ui.clear_screen()?;
+                } else if ch == ' '
+                    && self.command.is_empty()
+                    && matches!(self.app_mode, AppMode::Normal | AppMode::Find)
+                {
+                    self.toggle_mark();
+                } else if ch == ' ' && self.command.is_empty() && self.app_mode == AppMode::Dupes {
+                    self.toggle_dupe_mark();
+                } else if ch == ' ' && self.command.is_empty() && self.app_mode == AppMode::Permissions {
+                    self.activate_perm_row();
                 } else {
                     // self.command.push(ch);
                     self.command.insert(self.x_cursor as usize, ch);
@@ -434,6 +1106,50 @@ impl App {
                 }
             }
             KeyCode::Esc => {
+                // the first Esc only stops a running search
+                if let (AppMode::Find, Some(search)) = (&self.app_mode, &mut self.search) {
+                    if search.is_running() {
+                        search.cancel();
+                        return Ok(());
+                    }
+                }
+                if self.app_mode == AppMode::Find {
+                    self.search = None;
+                    self.marked.clear();
+                }
+                if let (AppMode::Grep, Some(grep)) = (&self.app_mode, &mut self.grep) {
+                    if grep.is_running() {
+                        grep.cancel();
+                        return Ok(());
+                    }
+                }
+                if self.app_mode == AppMode::Preview {
+                    self.app_mode = AppMode::Grep;
+                    self.app_ui.content_cursor = self.preview_hit;
+                    self.app_ui.content_render_from = self.preview_hit.saturating_sub(5);
+                    self.re_read = true;
+                    return Ok(());
+                }
+                if self.app_mode == AppMode::Grep {
+                    self.grep = None;
+                }
+                if self.conflict_prompt.take().is_some() {
+                    self.set_status("copy/move cancelled, nothing was changed");
+                }
+                if self.app_mode == AppMode::Dupes {
+                    self.marked.clear();
+                }
+                if self.app_mode == AppMode::DiskUsage {
+                    self.du_root = None;
+                    self.du_stack.clear();
+                }
+                if self.finder.take().is_some() {
+                    self.command.clear();
+                    self.x_cursor = 0;
+                }
+                if self.app_mode == AppMode::Normal && self.filter.is_active() {
+                    self.filter.clear();
+                }
                 self.app_mode = AppMode::Normal;
                 self.re_read = true;
             }
@@ -448,6 +1164,15 @@ impl App {
                 }
             }
 
+            KeyCode::Left if key_event.modifiers.contains(KeyModifiers::ALT) => self.go_back(),
+            KeyCode::Right if key_event.modifiers.contains(KeyModifiers::ALT) => self.go_forward(),
+            KeyCode::Left | KeyCode::Right
+                if self.command.is_empty()
+                    && self.app_mode == AppMode::Normal
+                    && self.config.tree_view =>
+            {
+                self.tree_key(key_event.code == KeyCode::Right)
+            }
             KeyCode::Left => {
                 if self.x_cursor > 0 {
                     self.x_cursor -= 1;
@@ -489,15 +1214,22 @@ impl App {
                         self.command
                             .insert_str(self.x_cursor as usize, &self.sugest);
                         self.x_cursor += self.sugest.len() as u16;
-                    } else {
-                        let path_selected = &self.content[self.app_ui.content_cursor];
+                    } else if let (AppMode::Normal, Some(path_selected)) =
+                        (&self.app_mode, self.content.get(self.app_ui.content_cursor))
+                    {
                         let mut ns = String::new();
 
                         if !path_selected.file_name.eq("../") {
                             ns.push_str("./");
                         }
 
-                        ns.push_str(&path_selected.file_name);
+                        // entries of the tree view sit below the current folder
+                        match path_selected.path.strip_prefix(&self.current_path) {
+                            Ok(rel) if path_selected.file_name != "../" => {
+                                ns.push_str(&rel.to_string_lossy())
+                            }
+                            _ => ns.push_str(&path_selected.file_name),
+                        }
                         if ns.contains(" ") {
                             ns = format!("\"{}\"", ns);
                         }
@@ -517,6 +1249,12 @@ impl App {
                     // }
                     self.config.bookmark.retain(|x| !x.eq(selected));
                     self.re_read = true;
+                } else if self.app_mode == AppMode::Trash {
+                    self.purge_selected_trash();
+                } else if self.app_mode == AppMode::Dupes {
+                    self.trash_dupes();
+                } else if self.app_mode == AppMode::DiskUsage {
+                    self.trash_du_selected();
                 }
             }
 
@@ -526,7 +1264,10 @@ impl App {
     
 
             KeyCode::Enter => {
-                if self.app_mode == AppMode::Bookmark {
+                if self.app_mode == AppMode::Fuzzy {
+                    self.open_finder_match();
+                    return Ok(());
+                } else if self.app_mode == AppMode::Bookmark {
                     if self.content_to_read.len() != 0 {
                         let selected = &self.content_to_read[self.app_ui.content_cursor];
                         self.command = format!("cd {}", selected.clone());
@@ -536,6 +1277,45 @@ impl App {
                     let selected = &self.content_to_read[self.app_ui.content_cursor];
                         self.command = selected.clone();
                         self.app_mode = AppMode::Normal;
+                } else if self.app_mode == AppMode::Permissions && self.command.trim().is_empty() {
+                    self.activate_perm_row();
+                    return Ok(());
+                } else if self.app_mode == AppMode::BulkRename && self.command.trim().is_empty() {
+                    self.apply_bulk_rename();
+                    return Ok(());
+                } else if self.app_mode == AppMode::Trash && self.command.trim().is_empty() {
+                    self.restore_selected_trash();
+                    return Ok(());
+                } else if self.app_mode == AppMode::Jump && self.command.trim().is_empty() {
+                    if let Some(path) = self.jump_choices.get(self.app_ui.content_cursor) {
+                        self.current_path = path.clone();
+                        self.app_mode = AppMode::Normal;
+                        self.re_read = true;
+                    }
+                    return Ok(());
+                } else if self.app_mode == AppMode::Grep && self.command.trim().is_empty() {
+                    self.preview_grep_hit();
+                    return Ok(());
+                } else if self.app_mode == AppMode::Preview && self.command.trim().is_empty() {
+                    self.edit_grep_hit()?;
+                    return Ok(());
+                } else if self.app_mode == AppMode::Find && self.command.trim().is_empty() {
+                    self.open_found(false);
+                    return Ok(());
+                } else if self.app_mode == AppMode::DiskUsage && self.command.trim().is_empty() {
+                    self.open_du_selected();
+                    return Ok(());
+                } else if self.app_mode == AppMode::Dupes && self.command.trim().is_empty() {
+                    self.open_dupe();
+                    return Ok(());
+                } else if matches!(self.app_mode, AppMode::Diff | AppMode::Checksums)
+                    && self.command.trim().is_empty()
+                {
+                    // read only views, there is nothing to open
+                    return Ok(());
+                } else if self.app_mode == AppMode::Conflict && self.command.trim().is_empty() {
+                    self.activate_conflict_row();
+                    return Ok(());
                 }
                 if self.is_ignore_exec {
                     self.open_dir()?;
@@ -550,6 +1330,14 @@ impl App {
             _ => {}
         }
 
+        // the command line is the query while the finder is open
+        if let (AppMode::Fuzzy, Some(finder)) = (&self.app_mode, &mut self.finder) {
+            if finder.set_query(&self.command) {
+                self.app_ui.content_cursor = 0;
+                self.re_read = true;
+            }
+        }
+
         Ok(())
     }
 
@@ -570,6 +1358,27 @@ impl App {
                 self.re_read = true;
             }
             ":t" | ":terminal" => self.app_mode = AppMode::TerminalOnly,
+            ":follow" => {
+                self.config.follow_links = !self.config.follow_links;
+                self.set_status(&format!("follow links: {}", self.config.follow_links));
+            }
+            ":tree" => {
+                self.config.tree_view = !self.config.tree_view;
+                self.set_status(&format!("tree view: {}", self.config.tree_view));
+                self.re_read = true;
+            }
+            ":realpath" | ":rp" => {
+                self.config.show_real_path = !self.config.show_real_path;
+                self.set_status(&format!("show real path: {}", self.config.show_real_path));
+            }
+            s if s.starts_with(":ln ") => {
+                let args = pathmanager::split_args(&s[4..]);
+                match args.as_slice() {
+                    [flag, name] if flag == "-h" => self.link_selected(&name.clone(), true)?,
+                    [name] => self.link_selected(&name.clone(), false)?,
+                    _ => self.set_error("usage: :ln [-h] <link name>"),
+                }
+            }
 
             ":bookmark add" | ":ba" => {
                 let path = self.current_path.to_string_lossy().into_owned();
@@ -607,22 +1416,136 @@ impl App {
                     self.re_read = true;
                 }
             }
-            s if s.starts_with(":nf") => {
-                let cd_args: Vec<&str> = s.split(" ").collect();
-                if cd_args.len() < 1 {
-                    self.decs_label = String::from("invalid argument");
+            s if matches!(s.split(' ').next(), Some(":nf") | Some(":mkdir")) => {
+                let is_dir = s.starts_with(":mkdir");
+                let args = s.split_once(' ').map(|(_, a)| a).unwrap_or("");
+                let names: Vec<String> = pathmanager::split_args(args)
+                    .iter()
+                    .flat_map(|a| pathmanager::expand_braces(a))
+                    .collect();
+
+                if names.is_empty() {
+                    self.set_error("usage: :nf <name>... or :mkdir <name>...");
                 } else {
-                    let nav_cmd = pathmanager::convert_path_to_nav(cd_args[1])?;
-                    let path = pathmanager::resolve_path(&self.current_path, &nav_cmd)?;
-
-                    filemanager::make_empty_file(path);
-
-                    self.re_read = true;
+                    self.create_paths(&names, is_dir)?;
+                }
+            }
+            s if s.starts_with(":cp ") || s.starts_with(":mv ") => {
+                let args = pathmanager::split_args(&s[4..]);
+                let is_move = s.starts_with(":mv");
+                if args.len() != 1 {
+                    self.set_error("usage: :cp <dest> or :mv <dest>");
+                } else {
+                    self.copy_or_move_selected(&args[0], is_move)?;
+                }
+            }
+            s if s.starts_with(":rename ") => {
+                let args = pathmanager::split_args(&s[8..]);
+                if args.len() != 1 {
+                    self.set_error("usage: :rename <new name>");
+                } else {
+                    self.rename_selected(&args[0]);
+                }
+            }
+            ":rm" if self.app_mode == AppMode::DiskUsage => self.trash_du_selected(),
+            ":rm" if self.app_mode == AppMode::Dupes => self.trash_dupes(),
+            ":rm" => self.trash_selected(),
+            ":bulkrename" | ":br" => self.bulk_rename()?,
+            ":cancel" => self.cancel_job(),
+            s if s == ":extract" || s.starts_with(":extract ") => {
+                let args = pathmanager::split_args(&s[8..]);
+                match args.as_slice() {
+                    [] => self.extract_selected(None)?,
+                    [dest] => self.extract_selected(Some(&dest.clone()))?,
+                    _ => self.set_error("usage: :extract [dest]"),
+                }
+            }
+            s if s.starts_with(":compress ") => {
+                let args = pathmanager::split_args(&s[10..]);
+                match args.as_slice() {
+                    [name] => self.compress_selected(&name.clone())?,
+                    _ => self.set_error("usage: :compress <name.tar.gz|.tar.xz|.tar|.zip>"),
+                }
+            }
+            ":perm" | ":chmod" => self.open_perm_dialog(),
+            s if s.starts_with(":chown ") || s.starts_with(":chgrp ") => {
+                let is_group = s.starts_with(":chgrp");
+                let name = s[7..].trim().to_string();
+                self.set_perm_owner(&name, is_group);
+            }
+            ":undo" | ":u" => {
+                match self.journal.undo() {
+                    Ok(msg) => self.set_status(&msg),
+                    Err(e) => self.set_error(&format!("{}", e)),
+                }
+                self.re_read = true;
+            }
+            ":redo" => {
+                match self.journal.redo() {
+                    Ok(msg) => self.set_status(&msg),
+                    Err(e) => self.set_error(&format!("{}", e)),
                 }
+                self.re_read = true;
+            }
+            ":trash" => {
+                self.app_mode = AppMode::Trash;
+                self.app_ui.content_cursor = 0;
+                self.app_ui.content_render_from = 0;
+                self.re_read = true;
+            }
+            s if s.starts_with(":z ") => {
+                let fragments = pathmanager::split_args(&s[3..]);
+                self.jump(&fragments)?;
+            }
+            ":back" => self.go_back(),
+            ":forward" => self.go_forward(),
+            ":dupes" => self.find_dupes(),
+            ":diff" => self.diff_marked(),
+            s if s == ":hash" || s.starts_with(":hash ") => {
+                let args = pathmanager::split_args(&s[5..]);
+                match args.as_slice() {
+                    [] => self.hash_selected(Algorithm::Sha256),
+                    [check] if check == "check" => self.verify_sums("SHA256SUMS"),
+                    [check, file] if check == "check" => self.verify_sums(&file.clone()),
+                    [name] if Algorithm::parse(name).is_some() => {
+                        self.hash_selected(Algorithm::parse(name).unwrap_or(Algorithm::Sha256))
+                    }
+                    _ => self.set_error("usage: :hash [md5|sha1|sha256|blake3] or :hash check [SHA256SUMS]"),
+                }
+            }
+            ":du" => self.scan_disk_usage(),
+            s if s.starts_with(":find ") => {
+                let args = pathmanager::split_args(&s[6..]);
+                self.start_search(&args);
             }
+            ":goto" if self.app_mode == AppMode::Find => self.open_found(true),
+            s if s.starts_with(":grep ") => {
+                let args = pathmanager::split_args(&s[6..]);
+                self.start_grep(&args);
+            }
+            ":edit" if matches!(self.app_mode, AppMode::Grep | AppMode::Preview) => {
+                self.edit_grep_hit()?
+            }
+            s @ (":sort name" | ":sort size") => {
+                self.config.sort_by_size = s == ":sort size";
+                self.re_read = true;
+            }
+            ":select all" | ":sa" if self.app_mode == AppMode::Dupes => self.mark_redundant_dupes(),
+            ":select all" | ":sa" => self.mark_all(false),
+            ":select invert" | ":si" => self.mark_all(true),
+            ":select clear" | ":sc" => self.marked.clear(),
+            ":yank" | ":y" => self.yank_selected(false),
+            ":cut" | ":x" => self.yank_selected(true),
+            ":paste" | ":p" => self.paste_clipboard(),
             _ => {
+                let cmd = self.expand_marked(&self.command);
                 if self.app_mode == AppMode::TerminalOnly {
-                    self.app_term.run(self.command.clone())?;
+                    self.app_term.run(cmd)?;
+                    // the next command starts where this one ended
+                    if let Some(dir) = self.app_term.running_path.take() {
+                        env::set_current_dir(&dir)?;
+                        self.current_path = dir;
+                    }
                 } else {
                     // self.app_ui.end()?;
                     self.app_ui.set_alternate_screen(false)?;
@@ -632,13 +1555,16 @@ impl App {
                         // &self.command,
                     )?;
 
-                    print!("{} \n", &self.command);
-                    self.app_term.run(self.command.clone())?;
+                    println!("{} ", &cmd);
+                    self.app_term.run(cmd)?;
                     self.app_ui.print_term_end()?;
                     io::stdin().read_line(&mut String::new())?;
 
                     self.app_ui.set_alternate_screen(true)?;
-                    self.current_path = env::current_dir()?;
+                    self.current_path = match self.app_term.running_path.take() {
+                        Some(dir) => dir,
+                        None => env::current_dir()?,
+                    };
 
                     // self.current_path = self.app_term.running_path.clone();
                 }
@@ -651,16 +1577,1191 @@ impl App {
         Ok(())
     }
 
-    pub fn end(&mut self) -> io::Result<()> {
-        self.app_ui.end()?;
-        self.config.set_working_path(&self.current_path);
-        self.config.command_history = self.command_history.clone();
-        self.config.save()?;
-        println!(
-            "JOURNAL:\n\nlast path :\n{}",
-            self.current_path.display(),
-            // self.temp_path.display()
-        );
+    fn copy_or_move_selected(&mut self, dest: &str, is_move: bool) -> io::Result<()> {
+        let targets = self.target_paths();
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return Ok(());
+        }
+
+        let dest = pathmanager::resolve_arg(&self.current_path, dest)?;
+        if targets.len() > 1 && !dest.is_dir() {
+            self.set_error(&format!("{} is not a directory", dest.display()));
+            return Ok(());
+        }
+
+        self.transfer_paths(&targets, &dest, is_move);
+        if is_move {
+            self.marked.clear();
+        }
         Ok(())
     }
+
+    // copy or move every source into dest on a worker thread. existing
+    // targets are handled by the conflict policy, the prompt asks first
+    // when it is "ask"
+    fn transfer_paths(&mut self, sources: &[PathBuf], dest: &Path, is_move: bool) {
+        if self.job.is_some() {
+            self.set_error("another copy or move is running, ^x cancels it");
+            return;
+        }
+
+        let mut prompt = ConflictPrompt {
+            is_move,
+            transfers: Vec::new(),
+            conflicts: Vec::new(),
+            skipped: 0,
+            apply_all: false,
+        };
+        for from in sources.iter() {
+            let to = filemanager::destination_for(from, dest);
+            if to == *from {
+                // pasting in the same folder makes a copy next to it
+                if is_move {
+                    prompt.skipped += 1;
+                } else {
+                    let to = conflict::free_name(&to);
+                    prompt.transfers.push(Transfer { from: from.clone(), to, replace: false });
+                }
+            } else if to.symlink_metadata().is_err() {
+                prompt.transfers.push(Transfer { from: from.clone(), to, replace: false });
+            } else if self.config.conflict_policy == ConflictPolicy::Ask {
+                prompt.conflicts.push((from.clone(), to));
+            } else {
+                match conflict::resolve(self.config.conflict_policy, from.clone(), to) {
+                    Some(transfer) => prompt.transfers.push(transfer),
+                    None => prompt.skipped += 1,
+                }
+            }
+        }
+
+        if !prompt.conflicts.is_empty() {
+            self.conflict_prompt = Some(prompt);
+            self.app_mode = AppMode::Conflict;
+            self.app_ui.content_cursor = 0;
+            self.app_ui.content_render_from = 0;
+            self.re_read = true;
+            return;
+        }
+        self.start_transfer(prompt);
+    }
+
+    fn start_transfer(&mut self, prompt: ConflictPrompt) {
+        if prompt.transfers.is_empty() {
+            self.set_error(&format!("nothing to do, skipped {} item(s)", prompt.skipped));
+            return;
+        }
+
+        // moved files are gone from their old place, don't paste them twice
+        if prompt.is_move {
+            let moved: HashSet<&PathBuf> = prompt.transfers.iter().map(|t| &t.from).collect();
+            self.clipboard.retain(|p| !moved.contains(p));
+            if self.clipboard.is_empty() {
+                self.clipboard_cut = false;
+            }
+        }
+
+        self.job = Some(Job::spawn_transfer(prompt.transfers, prompt.is_move, prompt.skipped));
+    }
+
+    fn activate_conflict_row(&mut self) {
+        let Some(prompt) = self.conflict_prompt.as_mut() else {
+            return;
+        };
+
+        if prompt.activate(self.app_ui.content_cursor) {
+            if let Some(prompt) = self.conflict_prompt.take() {
+                self.app_mode = AppMode::Normal;
+                self.start_transfer(prompt);
+            }
+        }
+        self.re_read = true;
+    }
+
+    // called from the main loop while a job runs
+    fn poll_job(&mut self) {
+        let Some(job) = self.job.as_mut() else {
+            return;
+        };
+        let Some(report) = job.poll() else {
+            return;
+        };
+        let (is_move, skipped) = (job.is_move, job.skipped);
+        self.job = None;
+        self.finish_job(report, is_move, skipped);
+    }
+
+    fn finish_job(&mut self, report: JobReport, is_move: bool, skipped: usize) {
+        for op in report.ops {
+            self.journal.record(op);
+        }
+
+        let verb = if is_move { "moved" } else { "copied" };
+        let mut msg = format!("{} {} item(s)", verb, report.done);
+        if skipped > 0 {
+            msg.push_str(&format!(", skipped {}", skipped));
+        }
+
+        if report.cancelled {
+            self.set_error(&format!("cancelled, {}", msg));
+        } else if let Some(e) = report.errors.last() {
+            self.set_error(&format!("{}, {} failed: {}", msg, report.errors.len(), e));
+        } else if skipped > 0 {
+            self.set_error(&msg);
+        } else {
+            self.set_status(&msg);
+        }
+        self.re_read = true;
+    }
+
+    fn cancel_job(&mut self) {
+        // a scan goes first, it is the cheaper one to start again
+        if let Some(scan) = &self.scan {
+            scan.cancel();
+            self.set_status("cancelling...");
+            return;
+        }
+        match &self.job {
+            Some(job) => {
+                job.cancel();
+                self.set_status("cancelling...");
+            }
+            None => self.set_error("nothing is running"),
+        }
+    }
+
+    fn yank_selected(&mut self, is_cut: bool) {
+        let targets = self.target_paths();
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return;
+        }
+
+        self.set_status(&format!(
+            "{} {} item(s), :paste to {} here",
+            if is_cut { "cut" } else { "yanked" },
+            targets.len(),
+            if is_cut { "move" } else { "copy" }
+        ));
+        self.clipboard = targets;
+        self.clipboard_cut = is_cut;
+        self.marked.clear();
+    }
+
+    fn paste_clipboard(&mut self) {
+        if self.clipboard.is_empty() {
+            self.set_error("clipboard is empty, :yank or :cut something first");
+            return;
+        }
+
+        let sources = self.clipboard.clone();
+        let dest = self.current_path.clone();
+        self.transfer_paths(&sources, &dest, self.clipboard_cut);
+    }
+
+    fn create_paths(&mut self, names: &[String], is_dir: bool) -> io::Result<()> {
+        let mut done = 0;
+        let mut last_error = None;
+
+        for name in names.iter() {
+            let path = pathmanager::resolve_arg(&self.current_path, name)?;
+
+            match self.journal.create(&path, is_dir) {
+                Ok(_) => done += 1,
+                Err(e) => last_error = Some(format!("cannot create {}: {}", name, e)),
+            }
+        }
+
+        let kind = if is_dir { "folder(s)" } else { "file(s)" };
+        match last_error {
+            Some(e) => self.set_error(&format!("{}/{} created, {}", done, names.len(), e)),
+            None => self.set_status(&format!("created {} {}", done, kind)),
+        }
+        self.re_read = true;
+        Ok(())
+    }
+
+    // link to the selected entry, created relative to the current folder
+    fn link_selected(&mut self, name: &str, hard: bool) -> io::Result<()> {
+        let Some(item) = self.selected_item() else {
+            self.set_error("nothing selected");
+            return Ok(());
+        };
+        let target = item.path.clone();
+
+        let link = pathmanager::resolve_arg(&self.current_path, name)?;
+        let link = filemanager::destination_for(&target, &link);
+
+        match self.journal.link(&target, &link, hard) {
+            Ok(_) => self.set_status(&format!("linked {} -> {}", link.display(), target.display())),
+            Err(e) => self.set_error(&format!("cannot link: {}", e)),
+        }
+        self.re_read = true;
+        Ok(())
+    }
+
+    fn rename_selected(&mut self, new_name: &str) {
+        let Some(item) = self.selected_item() else {
+            self.set_error("nothing selected");
+            return;
+        };
+
+        match self.journal.rename(&item.path.clone(), new_name) {
+            Ok(to) => self.set_status(&format!("renamed to {}", to.display())),
+            Err(e) => self.set_error(&format!("cannot rename: {}", e)),
+        }
+        self.re_read = true;
+    }
+
+    fn bulk_rename(&mut self) -> io::Result<()> {
+        let paths: Vec<PathBuf> = if self.marked.is_empty() {
+            self.listing()
+                .iter()
+                .filter(|i| i.file_name != "../")
+                .map(|i| i.path.clone())
+                .collect()
+        } else {
+            self.target_paths()
+        };
+        let names: Vec<String> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap_or_default().to_string_lossy().into_owned())
+            .collect();
+
+        if names.is_empty() {
+            self.set_error("nothing to rename");
+            return Ok(());
+        }
+        if names.iter().any(|n| n.contains('\n')) {
+            self.set_error("cannot bulk rename file names with line breaks");
+            return Ok(());
+        }
+
+        self.app_ui.set_alternate_screen(false)?;
+        let edited = bulkrename::edit_names(&names);
+        self.app_ui.set_alternate_screen(true)?;
+        self.re_read = true;
+
+        let r = edited
+            .map_err(|e| format!("editor failed: {}", e))
+            .and_then(|new_names| bulkrename::plan(&paths, &new_names));
+
+        match r {
+            Ok(renames) if renames.is_empty() => self.set_status("no names changed"),
+            Ok(renames) => {
+                self.pending_renames = renames;
+                self.app_mode = AppMode::BulkRename;
+            }
+            Err(e) => self.set_error(&e),
+        }
+        Ok(())
+    }
+
+    fn apply_bulk_rename(&mut self) {
+        let renames = std::mem::take(&mut self.pending_renames);
+        let count = renames.len();
+
+        match self.journal.rename_all(renames) {
+            Ok(_) => self.set_status(&format!("renamed {} item(s), :undo to revert", count)),
+            Err(e) => self.set_error(&format!("cannot rename: {}", e)),
+        }
+        self.marked.clear();
+        self.app_mode = AppMode::Normal;
+        self.re_read = true;
+    }
+
+    // archives under the cursor (or marked) are unpacked, entries
+    // browsed inside an archive are copied out of it
+    fn extract_selected(&mut self, dest: Option<&str>) -> io::Result<()> {
+        let targets = self.target_paths();
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return Ok(());
+        }
+
+        let dest = match dest {
+            Some(d) => Some(pathmanager::resolve_arg(&self.current_path, d)?),
+            None => None,
+        };
+
+        let mut done = 0;
+        let mut last_error = None;
+        for path in targets.iter() {
+            let r = match archive::split_path(path) {
+                Some((archive_path, inner)) if !inner.as_os_str().is_empty() => {
+                    let dest = dest.clone().unwrap_or(
+                        archive_path.parent().map(|p| p.to_path_buf()).unwrap_or_default(),
+                    );
+                    archive::extract_entry(&archive_path, &inner, &dest).map(|_| ())
+                }
+                Some((archive_path, _)) => {
+                    let dest = dest.clone().unwrap_or(self.current_path.clone());
+                    let mut progress = |p: &Path| {
+                        self.show_progress(&format!("extracting {}", p.display()));
+                    };
+                    archive::extract_all(&archive_path, &dest, &mut progress).map(|_| ())
+                }
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "not an archive",
+                )),
+            };
+
+            match r {
+                Ok(_) => done += 1,
+                Err(e) => last_error = Some(format!("cannot extract {}: {}", path.display(), e)),
+            }
+        }
+
+        match last_error {
+            Some(e) => self.set_error(&format!("{}/{} extracted, {}", done, targets.len(), e)),
+            None => self.set_status(&format!("extracted {} item(s)", done)),
+        }
+        self.re_read = true;
+        Ok(())
+    }
+
+    fn compress_selected(&mut self, name: &str) -> io::Result<()> {
+        let targets = self.target_paths();
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return Ok(());
+        }
+
+        let archive_path = pathmanager::resolve_arg(&self.current_path, name)?;
+
+        let mut count = 0;
+        let mut progress = |p: &Path| {
+            count += 1;
+            self.show_progress(&format!("compressing {}: {}", count, p.display()));
+        };
+        match archive::create(&archive_path, &targets, &mut progress) {
+            Ok(_) => self.set_status(&format!("created {}", archive_path.display())),
+            Err(e) => self.set_error(&format!("cannot compress: {}", e)),
+        }
+        self.marked.clear();
+        self.re_read = true;
+        Ok(())
+    }
+
+    // hashing runs on a worker, poll_scan opens the view once it is done
+    fn find_dupes(&mut self) {
+        if self.scan.is_some() {
+            self.set_error("another scan is running, ^x cancels it");
+            return;
+        }
+        let root = self.current_path.clone();
+        let hide_hidden_file = self.config.hide_hidden_file;
+        self.scan = Some(Scan::spawn(move |progress| {
+            Scanned::Dupes(dupes::find(&root, hide_hidden_file, progress))
+        }));
+    }
+
+    fn show_dupes(&mut self, groups: Vec<DupeGroup>) {
+        if groups.is_empty() {
+            self.set_status("no duplicate files found");
+            return;
+        }
+        self.dupe_groups = groups;
+        self.marked.clear();
+        self.app_mode = AppMode::Dupes;
+        self.app_ui.content_cursor = 0;
+        self.app_ui.content_render_from = 0;
+        self.re_read = true;
+    }
+
+    fn toggle_dupe_mark(&mut self) {
+        if let Some(Some(path)) = self.dupe_rows.get(self.app_ui.content_cursor) {
+            if !self.marked.remove(path) {
+                self.marked.insert(path.clone());
+            }
+        }
+        if self.app_ui.content_cursor + 1 < self.dupe_rows.len() {
+            self.app_ui.content_cursor += 1;
+        }
+    }
+
+    // keep the first copy of every group
+    fn mark_redundant_dupes(&mut self) {
+        for group in self.dupe_groups.iter() {
+            self.marked.extend(group.paths.iter().skip(1).cloned());
+        }
+    }
+
+    fn trash_dupes(&mut self) {
+        let targets: Vec<PathBuf> = if self.marked.is_empty() {
+            self.dupe_rows.get(self.app_ui.content_cursor).cloned().flatten().into_iter().collect()
+        } else {
+            self.dupe_rows.iter().flatten().filter(|p| self.marked.contains(*p)).cloned().collect()
+        };
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return;
+        }
+
+        let mut trashed = HashSet::new();
+        let mut last_error = None;
+        for path in targets.iter() {
+            match self.journal.trash_path(path) {
+                Ok(_) => {
+                    trashed.insert(path.clone());
+                }
+                Err(e) => last_error = Some(format!("cannot trash {}: {}", path.display(), e)),
+            }
+        }
+
+        for group in self.dupe_groups.iter_mut() {
+            group.paths.retain(|p| !trashed.contains(p));
+        }
+        self.dupe_groups.retain(|g| g.paths.len() > 1);
+
+        match last_error {
+            Some(e) => self.set_error(&format!("{}/{} trashed, {}", trashed.len(), targets.len(), e)),
+            None => self.set_status(&format!("moved {} item(s) to trash, :trash to restore", trashed.len())),
+        }
+        self.marked.clear();
+        self.re_read = true;
+    }
+
+    // go to the folder of the file under the cursor
+    fn open_dupe(&mut self) {
+        let Some(Some(path)) = self.dupe_rows.get(self.app_ui.content_cursor) else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            self.current_path = parent.to_path_buf();
+            self.focus_path = Some(path.clone());
+            self.marked.clear();
+            self.app_mode = AppMode::Normal;
+            self.re_read = true;
+        }
+    }
+
+    fn hash_selected(&mut self, algorithm: Algorithm) {
+        let targets = self.target_paths();
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return;
+        }
+
+        let mut rows = Vec::new();
+        let count = targets.len();
+        for (i, path) in targets.iter().enumerate() {
+            let name = path.strip_prefix(&self.current_path).unwrap_or(path).display().to_string();
+            if path.is_dir() {
+                rows.push((format!("{}: is a folder", name), Some("\x1b[31m")));
+                continue;
+            }
+
+            let total = path.metadata().map(|m| m.len()).unwrap_or(0).max(1);
+            let mut progress = |done: u64| {
+                self.show_progress(&format!("{} {}/{} {} {}%", algorithm.name(), i + 1, count, name, done * 100 / total));
+            };
+            match checksum::digest(path, algorithm, &mut progress) {
+                Ok(hex) => rows.push((format!("{}  {}", hex, name), None)),
+                Err(e) => rows.push((format!("{}: {}", name, e), Some("\x1b[31m"))),
+            }
+        }
+
+        self.checksum_title = format!("{} of {} file(s)", algorithm.name(), count);
+        self.show_checksums(rows);
+    }
+
+    // check every line of a SHA256SUMS style file in the current folder
+    fn verify_sums(&mut self, file: &str) {
+        let sums_path = self.current_path.join(file);
+        let content = match fs::read_to_string(&sums_path) {
+            Ok(c) => c,
+            Err(e) => {
+                self.set_error(&format!("cannot read {}: {}", sums_path.display(), e));
+                return;
+            }
+        };
+        let algorithm = Algorithm::of_sums_file(&sums_path);
+        let entries = checksum::parse_sums(&content);
+        if entries.is_empty() {
+            self.set_error(&format!("no checksums found in {}", file));
+            return;
+        }
+
+        let mut rows = Vec::new();
+        let mut failed = 0;
+        for (i, (expected, name)) in entries.iter().enumerate() {
+            let path = self.current_path.join(name);
+            let mut progress = |_: u64| {
+                self.show_progress(&format!("verifying {}/{} {}", i + 1, entries.len(), name));
+            };
+            match checksum::digest(&path, algorithm, &mut progress) {
+                Ok(hex) if hex == *expected => rows.push((format!("OK      {}", name), Some("\x1b[32m"))),
+                Ok(_) => {
+                    failed += 1;
+                    rows.push((format!("FAILED  {}", name), Some("\x1b[31m")));
+                }
+                Err(e) => {
+                    failed += 1;
+                    rows.push((format!("FAILED  {} ({})", name, e), Some("\x1b[31m")));
+                }
+            }
+        }
+
+        self.checksum_title = if failed == 0 {
+            format!("\x1b[32mall {} OK\x1b[0m ({})", entries.len(), algorithm.name())
+        } else {
+            format!("\x1b[31m{} of {} FAILED\x1b[0m ({})", failed, entries.len(), algorithm.name())
+        };
+        self.show_checksums(rows);
+    }
+
+    fn show_checksums(&mut self, rows: Vec<(String, Option<&'static str>)>) {
+        self.checksum_rows = rows;
+        self.app_mode = AppMode::Checksums;
+        self.app_ui.content_cursor = 0;
+        self.app_ui.content_render_from = 0;
+        self.re_read = true;
+    }
+
+    // like zoxide, the most frecent folder matching every fragment
+    fn jump(&mut self, fragments: &[String]) -> io::Result<()> {
+        // "~/work", "../x" or "/srv" are paths, not fragments
+        let mut resolved = Vec::new();
+        for fragment in fragments.iter() {
+            if fragment.starts_with(['~', '/', '.']) || fragment == "$HOME" {
+                let path = pathmanager::resolve_arg(&self.current_path, fragment)?;
+                resolved.push(path.to_string_lossy().into_owned());
+            } else {
+                resolved.push(fragment.clone());
+            }
+        }
+
+        if let [only] = resolved.as_slice() {
+            let path = PathBuf::from(only);
+            if path.is_absolute() && path.is_dir() {
+                self.current_path = path;
+                self.re_read = true;
+                return Ok(());
+            }
+        }
+
+        let matches: Vec<(PathBuf, f64)> = self
+            .config
+            .frecency
+            .query(&resolved)
+            .into_iter()
+            .filter(|(p, _)| *p != self.current_path)
+            .collect();
+        let Some((_, best)) = matches.first() else {
+            self.set_error(&format!("no visited folder matches {}", fragments.join(" ")));
+            return Ok(());
+        };
+
+        let best = *best;
+        let ties: Vec<PathBuf> = matches
+            .into_iter()
+            .take_while(|(_, score)| *score == best)
+            .map(|(p, _)| p)
+            .collect();
+        if let [path] = ties.as_slice() {
+            self.current_path = path.clone();
+        } else {
+            self.jump_choices = ties;
+            self.app_mode = AppMode::Jump;
+        }
+        self.re_read = true;
+        Ok(())
+    }
+
+    // "filter glob: *.rs  12 of 2000 shown"
+    fn filter_label(&self) -> String {
+        let shown = self.content.iter().filter(|i| i.file_name != "../").count();
+        let mut label = format!(
+            "\x1b[36mfilter {}: {}{}\x1b[0m {} of {} shown",
+            self.filter.kind.name(),
+            self.filter.text,
+            if self.filter_editing { "_" } else { "" },
+            shown,
+            self.filter_total,
+        );
+        if let Some(e) = &self.filter.error {
+            label.push_str(&format!(" \x1b[31m{}\x1b[0m", e));
+        }
+        label
+    }
+
+    // typing while the filter is edited, false lets the key through
+    fn filter_key(&mut self, key_event: KeyEvent) -> bool {
+        let mut text = self.filter.text.clone();
+        match key_event.code {
+            KeyCode::Char(ch) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                text.push(ch)
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Tab => {
+                let kind = self.filter.kind.next();
+                self.filter.set_kind(kind);
+                self.config.filter_kind = kind;
+            }
+            KeyCode::Enter => self.filter_editing = false,
+            KeyCode::Esc => {
+                self.filter_editing = false;
+                text.clear();
+            }
+            _ => return false,
+        }
+
+        if text != self.filter.text {
+            self.filter.set_text(&text);
+            // first match under the cursor
+            self.app_ui.content_cursor = usize::from(self.current_path != Path::new("/"));
+            self.app_ui.content_render_from = 0;
+        }
+        self.re_read = true;
+        true
+    }
+
+    fn start_search(&mut self, args: &[String]) {
+        let search = FindOptions::parse(args, self.config.hide_hidden_file)
+            .and_then(|options| Search::start(&self.current_path, options));
+        match search {
+            Ok(search) => {
+                self.search = Some(search);
+                self.marked.clear();
+                self.app_mode = AppMode::Find;
+                self.app_ui.content_cursor = 0;
+                self.app_ui.content_render_from = 0;
+                self.re_read = true;
+            }
+            Err(e) => self.set_error(&format!(
+                "{}, usage: :find [-d depth] [-t f|d] [-H] <pattern>",
+                e
+            )),
+        }
+    }
+
+    // like poll_grep and poll_finder: new results only add rows, so the
+    // screen is not cleared for them
+    fn poll_search(&mut self) {
+        if self.app_mode != AppMode::Find {
+            return;
+        }
+        if let Some(search) = self.search.as_mut() {
+            if search.poll() {
+                self.content_to_read = search.labels();
+            }
+        }
+    }
+
+    // folders and archives are entered, files are shown in their folder.
+    // `to_parent` always goes to the containing folder
+    fn open_found(&mut self, to_parent: bool) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        let path = item.path.clone();
+
+        if !to_parent && (item.is_dir || archive::kind_of(&path).is_some()) {
+            self.current_path = path;
+        } else {
+            if let Some(parent) = path.parent() {
+                self.current_path = parent.to_path_buf();
+            }
+            self.focus_path = Some(path);
+        }
+        self.search = None;
+        self.app_mode = AppMode::Normal;
+        self.re_read = true;
+    }
+
+    fn start_grep(&mut self, args: &[String]) {
+        let grep = GrepOptions::parse(args, self.config.grep_gitignore, self.config.hide_hidden_file)
+            .and_then(|options| Grep::start(&self.current_path, options));
+        match grep {
+            Ok(grep) => {
+                self.grep = Some(grep);
+                self.app_mode = AppMode::Grep;
+                self.app_ui.content_cursor = 0;
+                self.app_ui.content_render_from = 0;
+                self.re_read = true;
+            }
+            Err(e) => self.set_error(&format!("{}, usage: :grep [-i] [-u] [-H] <regex>", e)),
+        }
+    }
+
+    fn grep_labels(&self) -> Vec<String> {
+        self.grep
+            .as_ref()
+            .map(|g| g.rows().into_iter().map(|(label, _)| label).collect())
+            .unwrap_or_default()
+    }
+
+    fn poll_grep(&mut self) {
+        if self.app_mode != AppMode::Grep {
+            return;
+        }
+        if self.grep.as_mut().is_some_and(|g| g.poll()) {
+            self.content_to_read = self.grep_labels();
+        }
+    }
+
+    // the whole file numbered, scrolled so the hit line is near the top
+    fn preview_grep_hit(&mut self) {
+        let row = self.app_ui.content_cursor;
+        let Some(hit) = self.grep.as_ref().and_then(|g| g.hits.get(row)) else {
+            return;
+        };
+        let data = match fs::read(&hit.path) {
+            Ok(data) => data,
+            Err(e) => {
+                self.set_error(&format!("cannot read {}: {}", hit.path.display(), e));
+                return;
+            }
+        };
+        let line = hit.line;
+
+        self.preview_rows = String::from_utf8_lossy(&data)
+            .lines()
+            .enumerate()
+            .map(|(i, l)| format!("{:>5} | {}", i + 1, l.replace('\t', "    ")))
+            .collect();
+        self.preview_hit = row;
+        self.app_mode = AppMode::Preview;
+        self.app_ui.content_cursor = line - 1;
+        self.app_ui.content_render_from = line.saturating_sub(5);
+        self.re_read = true;
+    }
+
+    // $EDITOR +line file, the line under the cursor in the preview
+    fn edit_grep_hit(&mut self) -> io::Result<()> {
+        let (row, line) = if self.app_mode == AppMode::Preview {
+            (self.preview_hit, Some(self.app_ui.content_cursor + 1))
+        } else {
+            (self.app_ui.content_cursor, None)
+        };
+        let Some(hit) = self.grep.as_ref().and_then(|g| g.hits.get(row)) else {
+            return Ok(());
+        };
+        let (path, line) = (hit.path.clone(), line.unwrap_or(hit.line));
+
+        self.app_ui.set_alternate_screen(false)?;
+        let res = termin::edit(&path, line);
+        self.app_ui.set_alternate_screen(true)?;
+        if let Err(e) = res {
+            self.set_error(&format!("cannot start the editor: {}", e));
+        }
+        self.re_read = true;
+        Ok(())
+    }
+
+    // Right expands the folder under the cursor, Left collapses it
+    // or moves up to the folder the entry is in
+    fn tree_key(&mut self, expand: bool) {
+        let cursor = self.app_ui.content_cursor;
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        let (path, is_dir) = (item.path.clone(), item.is_dir);
+
+        if expand {
+            if is_dir && !self.tree.is_expanded(&self.current_path, &path) {
+                self.tree.expand(&self.current_path, &path);
+                self.re_read = true;
+            }
+        } else if self.tree.collapse(&self.current_path, &path) {
+            self.re_read = true;
+        } else if let Some(depth) = self.tree_rows.get(cursor).map(|r| r.depth).filter(|d| *d > 0) {
+            if let Some(parent) = (0..cursor).rev().find(|i| self.tree_rows[*i].depth == depth - 1) {
+                self.app_ui.content_cursor = parent;
+            }
+        }
+    }
+
+    fn open_finder(&mut self) {
+        self.finder = Some(Finder::open(&self.current_path, self.config.hide_hidden_file));
+        self.app_mode = AppMode::Fuzzy;
+        self.command.clear();
+        self.x_cursor = 0;
+        self.app_ui.content_cursor = 0;
+        self.app_ui.content_render_from = 0;
+    }
+
+    fn finder_labels(&self) -> Vec<String> {
+        self.finder
+            .as_ref()
+            .map(|f| f.rows().into_iter().map(|(label, _)| label).collect())
+            .unwrap_or_default()
+    }
+
+    fn poll_finder(&mut self) {
+        if self.app_mode != AppMode::Fuzzy {
+            return;
+        }
+        if self.finder.as_mut().is_some_and(|f| f.poll()) {
+            self.content_to_read = self.finder_labels();
+        }
+    }
+
+    fn open_finder_match(&mut self) {
+        let Some(path) = self
+            .finder
+            .as_ref()
+            .and_then(|f| f.path(self.app_ui.content_cursor))
+        else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            self.current_path = parent.to_path_buf();
+        }
+        self.focus_path = Some(path);
+        self.finder = None;
+        self.command.clear();
+        self.x_cursor = 0;
+        self.app_mode = AppMode::Normal;
+        self.re_read = true;
+    }
+
+    fn go_back(&mut self) {
+        let from = self.current_path.clone();
+        match self.nav_history.back() {
+            Some(path) => {
+                self.current_path = path;
+                // coming back up, land on the folder we were in
+                self.focus_path = Some(from);
+                self.app_mode = AppMode::Normal;
+                self.re_read = true;
+            }
+            None => self.set_error("no earlier folder"),
+        }
+    }
+
+    fn go_forward(&mut self) {
+        match self.nav_history.forward() {
+            Some(path) => {
+                self.current_path = path;
+                self.app_mode = AppMode::Normal;
+                self.re_read = true;
+            }
+            None => self.set_error("no later folder"),
+        }
+    }
+
+    fn diff_marked(&mut self) {
+        let targets = self.target_paths();
+        let [left, right] = targets.as_slice() else {
+            self.set_error("mark exactly two files or two folders to compare");
+            return;
+        };
+
+        let rows = match (left.is_dir(), right.is_dir()) {
+            (true, true) => Ok(diff::diff_dirs(left, right)),
+            (false, false) => diff::diff_files(left, right),
+            _ => {
+                self.set_error("cannot compare a file with a folder");
+                return;
+            }
+        };
+        match rows {
+            Ok(rows) => {
+                self.diff_rows = rows;
+                self.app_mode = AppMode::Diff;
+                self.app_ui.content_cursor = 0;
+                self.app_ui.content_render_from = 0;
+                self.re_read = true;
+            }
+            Err(e) => self.set_error(&format!("cannot compare: {}", e)),
+        }
+    }
+
+    // the walk runs on a worker, poll_scan opens the view once it is done
+    fn scan_disk_usage(&mut self) {
+        if self.scan.is_some() {
+            self.set_error("another scan is running, ^x cancels it");
+            return;
+        }
+        let root = self.current_path.clone();
+        self.scan = Some(Scan::spawn(move |progress| {
+            Scanned::Usage(diskusage::scan(&root, progress))
+        }));
+    }
+
+    // called from the main loop while a scan runs
+    fn poll_scan(&mut self) {
+        let Some(res) = self.scan.as_mut().and_then(|s| s.poll()) else {
+            return;
+        };
+        self.scan = None;
+
+        match res {
+            None => self.set_status("scan cancelled"),
+            Some(Scanned::Usage(node)) => {
+                self.du_root = Some(node);
+                self.du_stack.clear();
+                self.app_mode = AppMode::DiskUsage;
+                self.app_ui.content_cursor = 0;
+                self.app_ui.content_render_from = 0;
+                self.re_read = true;
+            }
+            Some(Scanned::Dupes(groups)) => self.show_dupes(groups),
+        }
+    }
+
+    fn du_node(&self) -> Option<&DuNode> {
+        self.du_root.as_ref()?.descend(&self.du_stack)
+    }
+
+    // index into the children of du_node, None on the "../" row
+    fn du_selected(&self) -> Option<usize> {
+        if self.du_stack.is_empty() {
+            Some(self.app_ui.content_cursor)
+        } else {
+            self.app_ui.content_cursor.checked_sub(1)
+        }
+    }
+
+    // no rescan, the children are already there and sorted
+    fn open_du_selected(&mut self) {
+        match self.du_selected() {
+            None => {
+                if let Some(i) = self.du_stack.pop() {
+                    self.app_ui.content_cursor = i + usize::from(!self.du_stack.is_empty());
+                }
+            }
+            Some(i) => {
+                if !self.du_node().and_then(|n| n.children.get(i)).is_some_and(|c| c.is_dir) {
+                    return;
+                }
+                self.du_stack.push(i);
+                self.app_ui.content_cursor = 0;
+            }
+        }
+        self.app_ui.content_render_from = 0;
+        self.re_read = true;
+    }
+
+    // through the journal like :rm, so it can be undone
+    fn trash_du_selected(&mut self) {
+        let Some(i) = self.du_selected() else {
+            return;
+        };
+        let Some(path) = self.du_node().and_then(|n| n.children.get(i)).map(|c| c.path.clone()) else {
+            return;
+        };
+
+        match self.journal.trash_path(&path) {
+            Ok(_) => {
+                let mut indexes = self.du_stack.clone();
+                indexes.push(i);
+                let size = self.du_root.as_mut().and_then(|r| r.remove(&indexes)).map(|n| n.size);
+                self.set_status(&format!(
+                    "moved {} to trash, freed {}, :trash to restore",
+                    path.display(),
+                    filemanager::human_size(size.unwrap_or(0))
+                ));
+            }
+            Err(e) => self.set_error(&format!("cannot trash {}: {}", path.display(), e)),
+        }
+        self.re_read = true;
+    }
+
+    fn open_perm_dialog(&mut self) {
+        let Some(item) = self.selected_item() else {
+            self.set_error("nothing selected");
+            return;
+        };
+        // the dialog would show and change the target, not the link
+        if item.link_target.is_some() {
+            self.set_error(&format!("{} is a link, open its target instead", item.file_name));
+            return;
+        }
+
+        match PermDialog::open(&item.path.clone()) {
+            Ok(dialog) => {
+                self.perm_dialog = Some(dialog);
+                self.app_mode = AppMode::Permissions;
+                self.app_ui.content_cursor = 0;
+                self.app_ui.content_render_from = 0;
+            }
+            Err(e) => self.set_error(&format!("cannot read permissions: {}", e)),
+        }
+        self.re_read = true;
+    }
+
+    fn activate_perm_row(&mut self) {
+        let Some(dialog) = self.perm_dialog.as_mut() else {
+            return;
+        };
+
+        if dialog.activate(self.app_ui.content_cursor) {
+            let path = dialog.path.clone();
+            match dialog.apply() {
+                0 => self.set_status(&format!("permissions of {} changed", path.display())),
+                n => self.set_error(&format!("{} file(s) failed, see the list", n)),
+            }
+        }
+        self.re_read = true;
+    }
+
+    fn set_perm_owner(&mut self, name: &str, is_group: bool) {
+        let Some(dialog) = self.perm_dialog.as_mut().filter(|_| self.app_mode == AppMode::Permissions) else {
+            self.set_error("open the permission dialog with :perm first");
+            return;
+        };
+
+        let id = if is_group {
+            permissions::group_id(name)
+        } else {
+            permissions::user_id(name)
+        };
+        match (id, is_group) {
+            (Some(id), true) => dialog.gid = id,
+            (Some(id), false) => dialog.uid = id,
+            (None, _) => self.set_error(&format!("unknown {}: {}", if is_group { "group" } else { "user" }, name)),
+        }
+        self.re_read = true;
+    }
+
+    fn trash_selected(&mut self) {
+        let targets = self.target_paths();
+        if targets.is_empty() {
+            self.set_error("nothing selected");
+            return;
+        }
+
+        let mut done = 0;
+        let mut last_error = None;
+        for path in targets.iter() {
+            match self.journal.trash_path(path) {
+                Ok(_) => done += 1,
+                Err(e) => last_error = Some(format!("cannot trash {}: {}", path.display(), e)),
+            }
+        }
+
+        match last_error {
+            Some(e) => self.set_error(&format!("{}/{} trashed, {}", done, targets.len(), e)),
+            None => self.set_status(&format!("moved {} item(s) to trash, :trash to restore", done)),
+        }
+        self.marked.clear();
+        self.re_read = true;
+    }
+
+    fn restore_selected_trash(&mut self) {
+        let Some(item) = self.trash_items.get(self.app_ui.content_cursor) else {
+            return;
+        };
+
+        match trash::restore(item) {
+            Ok(path) => self.set_status(&format!("restored {}", path.display())),
+            Err(e) => self.set_error(&format!("cannot restore: {}", e)),
+        }
+        self.re_read = true;
+    }
+
+    fn purge_selected_trash(&mut self) {
+        let Some(item) = self.trash_items.get(self.app_ui.content_cursor) else {
+            return;
+        };
+
+        let msg = format!("delete {} forever? (y/n)", item.original_path.display());
+        self.purge_confirm = Some(item.clone());
+        self.set_status(&msg);
+    }
+
+    fn purge_trash(&mut self, item: &TrashItem) {
+        match trash::purge(item) {
+            Ok(_) => self.set_status(&format!("deleted {} forever", item.original_path.display())),
+            Err(e) => self.set_error(&format!("cannot delete: {}", e)),
+        }
+        self.re_read = true;
+    }
+
+    // marked entries in listing order, or the entry under the cursor
+    fn target_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self.selected_item().map(|i| vec![i.path.clone()]).unwrap_or_default();
+        }
+
+        self.listing()
+            .iter()
+            .filter(|i| self.marked.contains(&i.path))
+            .map(|i| i.path.clone())
+            .collect()
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        let path = item.path.clone();
+
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        if self.app_ui.content_cursor + 1 < self.listing().len() {
+            self.app_ui.content_cursor += 1;
+        }
+    }
+
+    fn mark_all(&mut self, invert: bool) {
+        let paths: Vec<PathBuf> = self
+            .listing()
+            .iter()
+            .filter(|i| i.file_name != "../")
+            .map(|i| i.path.clone())
+            .collect();
+        for path in paths {
+            if invert && self.marked.contains(&path) {
+                self.marked.remove(&path);
+            } else {
+                self.marked.insert(path);
+            }
+        }
+    }
+
+    // replace %m in a shell command with the quoted marked paths
+    fn expand_marked(&self, cmd: &str) -> String {
+        if !cmd.contains("%m") {
+            return cmd.to_string();
+        }
+
+        let paths: Vec<String> = self
+            .target_paths()
+            .iter()
+            .map(|p| format!("'{}'", p.to_string_lossy().replace('\'', "'\\''")))
+            .collect();
+        cmd.replace("%m", &paths.join(" "))
+    }
+
+    pub fn end(&mut self) -> io::Result<()> {
+        self.app_ui.end()?;
+
+        // don't leave a half copied file behind
+        if let Some(mut job) = self.job.take() {
+            job.cancel();
+            if let Some(report) = job.wait() {
+                self.finish_job(report, job.is_move, job.skipped);
+            }
+        }
+
+        self.config.set_working_path(&self.current_path);
+        self.config.command_history = self.command_history.clone();
+        self.config.save()?;
+        println!(
+            "JOURNAL:\n\nlast path :\n{}",
+            self.current_path.display(),
+            // self.temp_path.display()
+        );
+        Ok(())
+    }
+}
+
+// folders first, then by name or by size with SORT_BY_SIZE
+fn sort_items(items: &mut [ReadDirItems], by_size: bool, sizes: &DirSizes) {
+    items.sort_by_cached_key(|i| {
+        let size = match (by_size, i.is_dir) {
+            (false, _) => 0,
+            (true, true) => sizes.get(&i.path).unwrap_or(0),
+            (true, false) => i.path.symlink_metadata().map(|m| m.len()).unwrap_or(0),
+        };
+        (!i.is_dir, Reverse(size), i.label.clone())
+    });
 }
@@ -0,0 +1,341 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::filemanager;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DiffKind {
+    Header,
+    Hunk,
+    Context,
+    Added,
+    Removed,
+}
+
+pub type DiffRow = (String, DiffKind);
+
+const CONTEXT: usize = 3;
+// past this many changed lines the diff is not worth showing. the trace
+// kept for walking back grows with its square, 1000 stays around 8 MB
+const MAX_EDITS: usize = 1000;
+
+enum Edit {
+    Same(usize),
+    Removed(usize),
+    Added(usize),
+}
+
+// shortest edit script between a and b (myers), None when it is too long
+fn edits(a: &[&str], b: &[&str]) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // v around the diagonals of every round, for walking back
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        if d as usize > MAX_EDITS {
+            return None;
+        }
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) {
+                v[i + 1]
+            } else {
+                v[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut res = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            res.push(Edit::Same(x as usize - 1));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                res.push(Edit::Added(y as usize - 1));
+            } else {
+                res.push(Edit::Removed(x as usize - 1));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    res.reverse();
+    Some(res)
+}
+
+fn clean(line: &str) -> String {
+    line.trim_end_matches('\r').replace('\t', "    ")
+}
+
+// unified diff of two text files
+pub fn diff_files(left: &Path, right: &Path) -> io::Result<Vec<DiffRow>> {
+    let (a, b) = (fs::read(left)?, fs::read(right)?);
+    let mut rows = vec![
+        (format!("--- {}", left.display()), DiffKind::Header),
+        (format!("+++ {}", right.display()), DiffKind::Header),
+    ];
+
+    if a == b {
+        rows.push((String::from("files are identical"), DiffKind::Context));
+        return Ok(rows);
+    }
+    if filemanager::is_binary(&a) || filemanager::is_binary(&b) {
+        rows.push((String::from("binary files differ"), DiffKind::Hunk));
+        return Ok(rows);
+    }
+
+    let (a, b) = (String::from_utf8_lossy(&a), String::from_utf8_lossy(&b));
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    let Some(edits) = edits(&a, &b) else {
+        rows.push((
+            format!("more than {} changed lines, not shown", MAX_EDITS),
+            DiffKind::Hunk,
+        ));
+        return Ok(rows);
+    };
+
+    // a hunk is every change plus CONTEXT lines around it,
+    // changes closer than 2 * CONTEXT share one
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Same(..)))
+        .map(|(i, _)| i)
+        .collect();
+    let mut i = 0;
+    while i < changed.len() {
+        let start = changed[i].saturating_sub(CONTEXT);
+        let mut end = changed[i];
+        while i < changed.len() && changed[i] <= end + 2 * CONTEXT {
+            end = changed[i];
+            i += 1;
+        }
+        let end = (end + CONTEXT + 1).min(edits.len());
+        let hunk = &edits[start..end];
+
+        // line numbers where the hunk starts on each side
+        let (mut a_at, mut b_at) = (0, 0);
+        for e in edits[..start].iter() {
+            match e {
+                Edit::Same(..) => {
+                    a_at += 1;
+                    b_at += 1;
+                }
+                Edit::Removed(_) => a_at += 1,
+                Edit::Added(_) => b_at += 1,
+            }
+        }
+        let a_len = hunk.iter().filter(|e| !matches!(e, Edit::Added(_))).count();
+        let b_len = hunk
+            .iter()
+            .filter(|e| !matches!(e, Edit::Removed(_)))
+            .count();
+        // an empty side starts on the line before, like diff -u does
+        let a_start = a_at + usize::from(a_len > 0);
+        let b_start = b_at + usize::from(b_len > 0);
+        rows.push((
+            format!("@@ -{},{} +{},{} @@", a_start, a_len, b_start, b_len),
+            DiffKind::Hunk,
+        ));
+
+        for e in hunk {
+            rows.push(match e {
+                Edit::Same(x) => (format!(" {}", clean(a[*x])), DiffKind::Context),
+                Edit::Removed(x) => (format!("-{}", clean(a[*x])), DiffKind::Removed),
+                Edit::Added(y) => (format!("+{}", clean(b[*y])), DiffKind::Added),
+            });
+        }
+    }
+
+    Ok(rows)
+}
+
+// every path below root, relative to it
+fn walk(root: &Path) -> BTreeSet<PathBuf> {
+    let mut res = BTreeSet::new();
+    let mut stack = vec![PathBuf::new()];
+
+    while let Some(rel) = stack.pop() {
+        let Ok(entries) = fs::read_dir(root.join(&rel)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = rel.join(entry.file_name());
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                stack.push(path.clone());
+            }
+            res.insert(path);
+        }
+    }
+    res
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(ma), Ok(mb)) if ma.is_dir() && mb.is_dir() => true,
+        (Ok(ma), Ok(mb)) if ma.is_file() && mb.is_file() => {
+            ma.len() == mb.len() && fs::read(a).ok() == fs::read(b).ok()
+        }
+        (Ok(ma), Ok(mb)) if ma.is_symlink() && mb.is_symlink() => {
+            fs::read_link(a).ok() == fs::read_link(b).ok()
+        }
+        _ => false,
+    }
+}
+
+// entries found on one side only, or on both with other content
+pub fn diff_dirs(left: &Path, right: &Path) -> Vec<DiffRow> {
+    let (a, b) = (walk(left), walk(right));
+    let mut rows = vec![
+        (format!("--- {}", left.display()), DiffKind::Header),
+        (format!("+++ {}", right.display()), DiffKind::Header),
+    ];
+
+    for path in a.union(&b) {
+        // everything below a one sided folder is one sided too
+        if path
+            .ancestors()
+            .skip(1)
+            .any(|p| !p.as_os_str().is_empty() && (a.contains(p) != b.contains(p)))
+        {
+            continue;
+        }
+
+        match (a.contains(path), b.contains(path)) {
+            (true, false) => rows.push((
+                format!("- only left   {}", path.display()),
+                DiffKind::Removed,
+            )),
+            (false, true) => {
+                rows.push((format!("+ only right  {}", path.display()), DiffKind::Added))
+            }
+            _ if !same_content(&left.join(path), &right.join(path)) => {
+                rows.push((format!("~ differs     {}", path.display()), DiffKind::Hunk))
+            }
+            _ => {}
+        }
+    }
+
+    if rows.len() == 2 {
+        rows.push((String::from("folders are identical"), DiffKind::Context));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    // the edit script in diff -u notation
+    fn script(a: &[&str], b: &[&str]) -> Vec<String> {
+        edits(a, b)
+            .unwrap()
+            .iter()
+            .map(|e| match e {
+                Edit::Same(x) => format!(" {}", a[*x]),
+                Edit::Removed(x) => format!("-{}", a[*x]),
+                Edit::Added(y) => format!("+{}", b[*y]),
+            })
+            .collect()
+    }
+
+    fn diff_texts(a: &str, b: &str) -> Vec<String> {
+        let dir = TempDir::new("diff");
+        fs::write(dir.join("a"), a).unwrap();
+        fs::write(dir.join("b"), b).unwrap();
+        let rows = diff_files(&dir.join("a"), &dir.join("b")).unwrap();
+        // the --- and +++ headers hold the temp paths
+        rows.into_iter().skip(2).map(|(t, _)| t).collect()
+    }
+
+    #[test]
+    fn edits_is_a_shortest_script() {
+        assert_eq!(script(&["a", "b", "c"], &["a", "b", "c"]), [" a", " b", " c"]);
+        assert_eq!(script(&["a", "b", "c"], &["a", "x", "c"]), [" a", "-b", "+x", " c"]);
+        assert_eq!(script(&[], &["a"]), ["+a"]);
+        assert_eq!(script(&["a"], &[]), ["-a"]);
+        assert_eq!(
+            script(&["a", "b", "c", "a", "b", "b", "a"], &["c", "b", "a", "b", "a", "c"])
+                .iter()
+                .filter(|e| !e.starts_with(' '))
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    fn edits_gives_up_past_max_edits() {
+        let a: Vec<String> = (0..=MAX_EDITS).map(|i| format!("a{}", i)).collect();
+        let a: Vec<&str> = a.iter().map(|l| l.as_str()).collect();
+        assert!(edits(&a, &[]).is_none());
+        assert!(edits(&a[1..], &[]).is_some());
+    }
+
+    #[test]
+    fn hunks_have_context_and_line_numbers() {
+        let a: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let b: String = (1..=20)
+            .filter(|i| *i != 18)
+            .map(|i| if i == 5 { String::from("five\n") } else { format!("{}\n", i) })
+            .collect();
+        assert_eq!(
+            diff_texts(&a, &b),
+            [
+                "@@ -2,7 +2,7 @@", " 2", " 3", " 4", "-5", "+five", " 6", " 7", " 8",
+                "@@ -15,6 +15,5 @@", " 15", " 16", " 17", "-18", " 19", " 20",
+            ]
+        );
+    }
+
+    #[test]
+    fn close_changes_share_a_hunk() {
+        let a: String = (1..=12).map(|i| format!("{}\n", i)).collect();
+        let b: String = (1..=12)
+            .filter(|i| *i != 3)
+            .map(|i| if i == 9 { String::from("nine\n") } else { format!("{}\n", i) })
+            .collect();
+        let rows = diff_texts(&a, &b);
+        assert_eq!(rows.iter().filter(|r| r.starts_with("@@")).count(), 1);
+        assert_eq!(rows[0], "@@ -1,12 +1,11 @@");
+    }
+
+    #[test]
+    fn empty_side_starts_on_the_line_before() {
+        assert_eq!(diff_texts("", "x\n"), ["@@ -0,0 +1,1 @@", "+x"]);
+    }
+}
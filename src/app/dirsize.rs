@@ -0,0 +1,205 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::SystemTime,
+};
+
+struct Request {
+    path: PathBuf,
+    dev: u64,
+    generation: usize,
+}
+
+// recursive folder sizes, computed one by one on a thread and cached per path
+pub struct DirSizes {
+    // size and the folder mtime it was computed at
+    cache: HashMap<PathBuf, (u64, Option<SystemTime>)>,
+    pending: HashSet<PathBuf>,
+    // the folder on screen and its filesystem, see show
+    folder: PathBuf,
+    dev: u64,
+    // bumped when the folder changes, the worker drops older requests
+    generation: Arc<AtomicUsize>,
+    tx: Sender<Request>,
+    rx: Receiver<(PathBuf, u64, usize)>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::symlink_metadata(path).ok()?.modified().ok()
+}
+
+// like filemanager::tree_size, but other filesystems (/proc, mounted disks)
+// are left out like in :du. None once `stale` says nobody wants it anymore
+fn folder_size(path: &Path, dev: u64, stale: &dyn Fn() -> bool) -> Option<u64> {
+    if stale() {
+        return None;
+    }
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return Some(0);
+    };
+    if !meta.is_dir() {
+        return Some(meta.len());
+    }
+    if meta.dev() != dev {
+        return Some(0);
+    }
+
+    let mut size = 0;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            size += folder_size(&entry.path(), dev, stale)?;
+        }
+    }
+    Some(size)
+}
+
+impl DirSizes {
+    pub fn new() -> DirSizes {
+        let (tx, requests) = mpsc::channel::<Request>();
+        let (done, rx) = mpsc::channel();
+        let generation = Arc::new(AtomicUsize::new(0));
+        let current = generation.clone();
+
+        thread::spawn(move || {
+            for request in requests {
+                let stale = || current.load(Ordering::Relaxed) != request.generation;
+                let Some(size) = folder_size(&request.path, request.dev, &stale) else {
+                    continue;
+                };
+                if done.send((request.path, size, request.generation)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        DirSizes {
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            folder: PathBuf::new(),
+            dev: 0,
+            generation,
+            tx,
+            rx,
+        }
+    }
+
+    // a folder that changed directly since is computed again
+    pub fn get(&self, path: &Path) -> Option<u64> {
+        self.cache
+            .get(path)
+            .filter(|(_, mtime)| *mtime == modified(path))
+            .map(|(size, _)| *size)
+    }
+
+    // sizes are only wanted below `folder` from now on, whatever is still
+    // queued for the folder shown before is dropped
+    pub fn show(&mut self, folder: &Path) {
+        if self.folder == folder {
+            return;
+        }
+        self.folder = folder.to_path_buf();
+        self.dev = fs::metadata(folder).map(|m| m.dev()).unwrap_or(0);
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.pending.clear();
+    }
+
+    // false when path gets no size: links and mount points are not walked
+    pub fn request(&mut self, path: &Path) -> bool {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return false;
+        };
+        if !meta.is_dir() || meta.dev() != self.dev {
+            return false;
+        }
+        if self.get(path).is_some() || self.pending.contains(path) {
+            return true;
+        }
+
+        let request = Request {
+            path: path.to_path_buf(),
+            dev: self.dev,
+            generation: self.generation.load(Ordering::Relaxed),
+        };
+        if self.tx.send(request).is_ok() {
+            self.pending.insert(path.to_path_buf());
+        }
+        true
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // take in finished sizes, returns the folders that got one
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut res = Vec::new();
+        while let Ok((path, size, generation)) = self.rx.try_recv() {
+            // finished just before the folder changed
+            if generation != self.generation.load(Ordering::Relaxed) {
+                continue;
+            }
+            self.pending.remove(&path);
+            self.cache.insert(path.clone(), (size, modified(&path)));
+            res.push(path);
+        }
+        res
+    }
+
+    // changes deeper down don't touch the folder mtime, forget everything
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+    use std::time::Duration;
+
+    fn wait(sizes: &mut DirSizes) {
+        while sizes.is_busy() {
+            sizes.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn links_get_no_size() {
+        let dir = TempDir::new("dirsize-link");
+        fs::create_dir(dir.join("d")).unwrap();
+        fs::write(dir.join("d/f"), "1234").unwrap();
+        std::os::unix::fs::symlink(dir.join("d"), dir.join("link")).unwrap();
+
+        let mut sizes = DirSizes::new();
+        sizes.show(dir.path());
+        assert!(sizes.request(&dir.join("d")));
+        assert!(!sizes.request(&dir.join("link")));
+        wait(&mut sizes);
+        assert_eq!(sizes.get(&dir.join("d")), Some(4));
+        assert_eq!(sizes.get(&dir.join("link")), None);
+    }
+
+    #[test]
+    fn changing_folder_drops_pending_requests() {
+        let dir = TempDir::new("dirsize-stale");
+        fs::create_dir(dir.join("d")).unwrap();
+
+        let mut sizes = DirSizes::new();
+        sizes.show(dir.path());
+        sizes.request(&dir.join("d"));
+        sizes.show(&dir.join("d"));
+        assert!(!sizes.is_busy());
+        // an answer that was already on its way is not taken in
+        thread::sleep(Duration::from_millis(50));
+        assert!(sizes.poll().is_empty());
+    }
+}
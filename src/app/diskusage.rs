@@ -0,0 +1,108 @@
+use std::{
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use super::filemanager;
+
+// one entry of the disk usage tree, children biggest first
+pub struct DuNode {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    pub is_dir: bool,
+    pub children: Vec<DuNode>,
+}
+
+// walk everything below path once. links are not followed and other
+// filesystems (/proc, mounted disks) are left out. `progress` gets every
+// folder, the walk stops when it returns false
+pub fn scan(path: &Path, progress: &mut dyn FnMut(&Path) -> bool) -> DuNode {
+    let dev = fs::symlink_metadata(path).map(|m| m.dev()).unwrap_or(0);
+    scan_node(path, dev, progress)
+}
+
+fn scan_node(path: &Path, dev: u64, progress: &mut dyn FnMut(&Path) -> bool) -> DuNode {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or(path.to_string_lossy().into_owned());
+    let mut node = DuNode {
+        path: path.to_path_buf(),
+        name,
+        size: 0,
+        is_dir: false,
+        children: Vec::new(),
+    };
+
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return node;
+    };
+    if !meta.is_dir() {
+        node.size = meta.len();
+        return node;
+    }
+
+    node.is_dir = true;
+    if meta.dev() != dev {
+        return node;
+    }
+
+    if !progress(path) {
+        return node;
+    }
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            node.children.push(scan_node(&entry.path(), dev, progress));
+        }
+    }
+    node.size = node.children.iter().map(|c| c.size).sum();
+    node.children
+        .sort_by(|a, b| b.size.cmp(&a.size).then(a.name.cmp(&b.name)));
+    node
+}
+
+impl DuNode {
+    // follow child indexes down from this node
+    pub fn descend(&self, indexes: &[usize]) -> Option<&DuNode> {
+        let mut node = self;
+        for i in indexes {
+            node = node.children.get(*i)?;
+        }
+        Some(node)
+    }
+
+    // drop the entry at the end of `indexes`, sizes above are lowered
+    pub fn remove(&mut self, indexes: &[usize]) -> Option<DuNode> {
+        let (last, rest) = indexes.split_last()?;
+        let size = self.descend(indexes)?.size;
+
+        let mut node = self;
+        node.size -= size;
+        for i in rest {
+            node = &mut node.children[*i];
+            node.size -= size;
+        }
+        Some(node.children.remove(*last))
+    }
+
+    // [######    ]  62.1%   1.2 GiB  name/
+    pub fn row(&self, total: u64) -> String {
+        let ratio = if total > 0 {
+            self.size as f64 / total as f64
+        } else {
+            0.0
+        };
+        let filled = (ratio * 10.0).round() as usize;
+        format!(
+            "[{}{}] {:>5.1}% {:>10}  {}{}",
+            "#".repeat(filled),
+            " ".repeat(10 - filled),
+            ratio * 100.0,
+            filemanager::human_size(self.size),
+            self.name,
+            if self.is_dir { "/" } else { "" }
+        )
+    }
+}
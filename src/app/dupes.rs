@@ -0,0 +1,116 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Read},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+// files with the same content
+pub struct DupeGroup {
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DupeGroup {
+    // bytes freed when only one copy is kept
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+// every regular file below root with its size, links are not followed.
+// hard links to the same file are only listed once. None when `progress`
+// returned false for a folder
+fn walk(
+    root: &Path,
+    hide_hidden_file: bool,
+    progress: &mut dyn FnMut(&Path) -> bool,
+) -> Option<Vec<(PathBuf, u64)>> {
+    let mut res = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        if !progress(&dir) {
+            return None;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if hide_hidden_file && entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+
+            if meta.is_dir() {
+                stack.push(entry.path());
+            } else if meta.is_file() && seen.insert((meta.dev(), meta.ino())) {
+                res.push((entry.path(), meta.len()));
+            }
+        }
+    }
+    Some(res)
+}
+
+fn hash_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1024 * 1024];
+
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+// group files below root by size, then confirm with a hash of the content.
+// `progress` gets every folder walked and every file hashed, the search
+// stops with nothing found when it returns false. most wasted space first
+pub fn find(
+    root: &Path,
+    hide_hidden_file: bool,
+    progress: &mut dyn FnMut(&Path) -> bool,
+) -> Vec<DupeGroup> {
+    let Some(files) = walk(root, hide_hidden_file, progress) else {
+        return Vec::new();
+    };
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, size) in files {
+        // every empty file would be a duplicate of every other one
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (size, paths) in by_size.into_iter().filter(|(_, p)| p.len() > 1) {
+        let mut by_hash: HashMap<Vec<u8>, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            if !progress(&path) {
+                return Vec::new();
+            }
+            // unreadable files can't be confirmed, leave them out
+            if let Ok(hash) = hash_file(&path) {
+                by_hash.entry(hash).or_default().push(path);
+            }
+        }
+
+        for (_, mut paths) in by_hash.into_iter().filter(|(_, p)| p.len() > 1) {
+            paths.sort();
+            groups.push(DupeGroup { size, paths });
+        }
+    }
+
+    groups.sort_by(|a, b| b.wasted().cmp(&a.wasted()).then(a.paths.cmp(&b.paths)));
+    groups
+}
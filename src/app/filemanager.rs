@@ -2,13 +2,19 @@ extern crate chrono;
 
 // use chrono::{DateTime, Local};
 use std::{
+    collections::hash_map::RandomState,
     fs,
-    io::{self, Read},
-    path::PathBuf,
+    hash::{BuildHasher, Hasher},
+    io::{self, Read, Write},
+    os::unix::fs::{MetadataExt, OpenOptionsExt},
+    path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use super::pathmanager::{convert_path_to_nav, NavigationCommand};
+use super::{
+    archive,
+    pathmanager::{convert_path_to_nav, NavigationCommand},
+};
 
 // pub fn read_dir
 pub struct ReadDirItems {
@@ -16,11 +22,23 @@ pub struct ReadDirItems {
     pub navigation_type: NavigationCommand,
     pub label: String,
     pub file_name: String,
+    pub is_dir: bool,
+    // set when the entry is a symlink
+    pub link_target: Option<PathBuf>,
+    pub is_broken_link: bool,
 }
 
-pub fn read_dir(path: &PathBuf, hide_hidden_file: &bool) -> io::Result<Vec<ReadDirItems>> {
+pub fn read_dir(
+    path: &PathBuf,
+    hide_hidden_file: &bool,
+    listings: &mut archive::Listings,
+) -> io::Result<Vec<ReadDirItems>> {
     let mut res = Vec::new();
 
+    if let Some((archive_path, inner)) = archive::split_path(path) {
+        return read_archive_dir(path, &archive_path, &inner, hide_hidden_file, listings);
+    }
+
     if path.is_dir() && path.exists() {
         let entries = fs::read_dir(path);
 
@@ -45,11 +63,20 @@ pub fn read_dir(path: &PathBuf, hide_hidden_file: &bool) -> io::Result<Vec<ReadD
 
                 name.push_str(&file_name);
 
+                let link_target = fs::read_link(&path).ok();
+                let is_broken_link = link_target.is_some() && !path.exists();
+                if let Some(target) = &link_target {
+                    name.push_str(&format!(" -> {}", target.display()));
+                }
+
                 res.push(ReadDirItems {
                     navigation_type: convert_path_to_nav(path.clone().to_str().unwrap())?,
                     path: path.clone(),
                     label: name.clone(),
                     file_name: file_name.to_string(),
+                    is_dir: path.is_dir(),
+                    link_target,
+                    is_broken_link,
                 });
             }
         }
@@ -58,14 +85,77 @@ pub fn read_dir(path: &PathBuf, hide_hidden_file: &bool) -> io::Result<Vec<ReadD
     Ok(res)
 }
 
-pub fn make_empty_file(path: PathBuf) {
-    fs::write(path, "").unwrap();
+// an archive listed like a folder, `path` is the virtual archive.zip/inner path
+fn read_archive_dir(
+    path: &Path,
+    archive_path: &Path,
+    inner: &Path,
+    hide_hidden_file: &bool,
+    listings: &mut archive::Listings,
+) -> io::Result<Vec<ReadDirItems>> {
+    let mut res = Vec::new();
+
+    for entry in listings.list_dir(archive_path, inner)? {
+        let file_name = entry.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if *hide_hidden_file && file_name.starts_with('.') {
+            continue;
+        }
+
+        let icon = if entry.is_dir { "🖿 " } else { "📑 " };
+        let entry_path = path.join(&file_name);
+        res.push(ReadDirItems {
+            navigation_type: NavigationCommand::Absolute(entry_path.clone()),
+            path: entry_path,
+            label: format!("{}{}", icon, file_name),
+            file_name,
+            is_dir: entry.is_dir,
+            link_target: None,
+            is_broken_link: false,
+        });
+    }
+
+    Ok(res)
+}
+
+// parent folders are created when missing, an existing file is left alone
+pub fn make_empty_file(path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    Ok(())
+}
+
+pub fn make_dir(path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::create_dir(path)
+}
+
+// ancestors of path that do not exist yet, outermost first
+pub fn missing_parents(path: &Path) -> Vec<PathBuf> {
+    let mut res: Vec<PathBuf> = path
+        .ancestors()
+        .skip(1)
+        .take_while(|p| !p.as_os_str().is_empty() && p.symlink_metadata().is_err())
+        .map(|p| p.to_path_buf())
+        .collect();
+    res.reverse();
+    res
 }
 
 pub struct MetadataInfo {
     pub display_name: String,
     pub size: u64,
     pub modified: String,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub is_dir: bool,
 }
 pub fn metadata(path: &PathBuf) -> io::Result<MetadataInfo> {
     let metadata = fs::metadata(path)?;
@@ -84,6 +174,10 @@ pub fn metadata(path: &PathBuf) -> io::Result<MetadataInfo> {
         display_name,
         size,
         modified: format!("{:?}", modified),
+        mode: metadata.mode(),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        is_dir: metadata.is_dir(),
     })
 }
 
@@ -99,3 +193,303 @@ pub fn read_file(path: &PathBuf) -> io::Result<String> {
         Ok(String::new())
     }
 }
+
+// a NUL byte near the start, like git checks
+pub fn is_binary(data: &[u8]) -> bool {
+    data.iter().take(8000).any(|b| *b == 0)
+}
+
+// copy a file or a whole directory tree to `to`
+pub fn copy_path(from: &Path, to: &Path) -> io::Result<()> {
+    copy_path_with(from, to, &mut |_| true)
+}
+
+// like copy_path, `progress` gets the bytes written so far and
+// can stop the copy by returning false
+pub fn copy_path_with(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64) -> bool,
+) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+
+    if meta.is_dir() {
+        if to.starts_with(from) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot copy a directory into itself",
+            ));
+        }
+
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path_with(&entry.path(), &to.join(entry.file_name()), progress)?;
+        }
+        fs::set_permissions(to, meta.permissions())?;
+    } else if meta.file_type().is_symlink() {
+        let target = fs::read_link(from)?;
+        std::os::unix::fs::symlink(target, to)?;
+    } else {
+        let mut reader = fs::File::open(from)?;
+        let mut writer = fs::File::create(to)?;
+        let mut buf = vec![0; 1024 * 1024];
+
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+            if !progress(n as u64) {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+        }
+        fs::set_permissions(to, meta.permissions())?;
+    }
+
+    Ok(())
+}
+
+// rename when possible, fall back to copy + remove across filesystems
+pub fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    move_path_with(from, to, &mut |_| true)
+}
+
+pub fn move_path_with(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64) -> bool,
+) -> io::Result<()> {
+    if to.starts_with(from) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot move a directory into itself",
+        ));
+    }
+
+    match fs::rename(from, to) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_path_with(from, to, progress)?;
+            remove_path(from)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+// bytes used by a file or everything below a folder, links are not followed
+pub fn tree_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| tree_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+pub fn rename_path(path: &Path, new_name: &str) -> io::Result<PathBuf> {
+    if new_name.is_empty() || new_name.contains('/') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid file name: {}", new_name),
+        ));
+    }
+
+    let target = path.with_file_name(new_name);
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+pub fn remove_path(path: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+// where `from` should land when copied or moved to `dest`.
+// an existing directory means "into that directory"
+pub fn destination_for(from: &Path, dest: &Path) -> PathBuf {
+    if dest.is_dir() {
+        if let Some(name) = from.file_name() {
+            return dest.join(name);
+        }
+    }
+    dest.to_path_buf()
+}
+
+// a batch rename that failed half way and could not be put back fully
+#[derive(Debug)]
+pub struct PartialRename {
+    pub error: io::Error,
+    // where the moved paths are now, for the journal
+    pub done: Vec<(PathBuf, PathBuf)>,
+}
+
+// rename many paths at once. everything goes through a temporary name
+// first so swaps like a -> b, b -> a don't clobber each other. on an error
+// the steps done so far are put back in reverse, nothing is ever overwritten
+pub fn rename_all(renames: &[(PathBuf, PathBuf)]) -> Result<(), PartialRename> {
+    let mut steps = Vec::new();
+    let Err(error) = rename_steps(renames, &mut steps) else {
+        return Ok(());
+    };
+
+    while let Some((from, to)) = steps.pop() {
+        if rename_free(&to, &from).is_err() {
+            steps.push((from, to));
+            break;
+        }
+    }
+
+    // chain what is left into one move per path
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (from, to) in steps {
+        match done.iter_mut().find(|(_, now)| *now == from) {
+            Some(entry) => entry.1 = to,
+            None => done.push((from, to)),
+        }
+    }
+    Err(PartialRename { error, done })
+}
+
+fn rename_steps(
+    renames: &[(PathBuf, PathBuf)],
+    steps: &mut Vec<(PathBuf, PathBuf)>,
+) -> io::Result<()> {
+    let mut temps = Vec::new();
+    for (i, (from, _)) in renames.iter().enumerate() {
+        let temp = from.with_file_name(format!(".luru-rename-{}-{}", std::process::id(), i));
+        rename_free(from, &temp)?;
+        steps.push((from.clone(), temp.clone()));
+        temps.push(temp);
+    }
+
+    // every source is out of the way now, a taken target is someone else's
+    for (temp, (_, to)) in temps.into_iter().zip(renames.iter()) {
+        rename_free(&temp, to)?;
+        steps.push((temp, to.clone()));
+    }
+    Ok(())
+}
+
+// fs::rename, but never over an existing path
+fn rename_free(from: &Path, to: &Path) -> io::Result<()> {
+    if to.symlink_metadata().is_ok() {
+        let msg = format!("{} already exists", to.display());
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, msg));
+    }
+    fs::rename(from, to)
+}
+
+pub fn make_link(target: &Path, link: &Path, hard: bool) -> io::Result<()> {
+    if hard {
+        fs::hard_link(target, link)
+    } else {
+        std::os::unix::fs::symlink(target, link)
+    }
+}
+
+// a new file only we can read, under a name nobody can guess beforehand.
+// create_new fails instead of following a link someone left in the way
+pub fn make_temp_file(prefix: &str, suffix: &str) -> io::Result<(PathBuf, fs::File)> {
+    let dir = std::env::temp_dir();
+    loop {
+        // RandomState is seeded randomly for every process
+        let mut hasher = RandomState::new().build_hasher();
+        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH);
+        hasher.write_u128(now.unwrap_or_default().as_nanos());
+        let path = dir.join(format!("{}{:016x}{}", prefix, hasher.finish(), suffix));
+
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+        match file {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    #[test]
+    fn rename_all_handles_a_cycle() {
+        let dir = TempDir::new("cycle");
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        // a -> b -> c -> a
+        let renames = [
+            (dir.join("a"), dir.join("b")),
+            (dir.join("b"), dir.join("c")),
+            (dir.join("c"), dir.join("a")),
+        ];
+        rename_all(&renames).unwrap();
+        let contents: Vec<String> = ["a", "b", "c"]
+            .iter()
+            .map(|n| fs::read_to_string(dir.join(n)).unwrap())
+            .collect();
+        assert_eq!(contents, ["c", "a", "b"]);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn rename_all_puts_a_failed_swap_back() {
+        let dir = TempDir::new("swap-back");
+        for name in ["a", "b", "c", "taken"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        // the swap is done before c fails on the taken name
+        let renames = [
+            (dir.join("a"), dir.join("b")),
+            (dir.join("b"), dir.join("a")),
+            (dir.join("c"), dir.join("taken")),
+        ];
+        let partial = rename_all(&renames).unwrap_err();
+        assert!(partial.done.is_empty());
+        for name in ["a", "b", "c", "taken"] {
+            assert_eq!(fs::read_to_string(dir.join(name)).unwrap(), name);
+        }
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+}
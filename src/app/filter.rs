@@ -0,0 +1,130 @@
+use glob::{MatchOptions, Pattern};
+use regex::{Regex, RegexBuilder};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl FilterKind {
+    pub fn parse(s: &str) -> Option<FilterKind> {
+        match s {
+            "substring" => Some(FilterKind::Substring),
+            "glob" => Some(FilterKind::Glob),
+            "regex" => Some(FilterKind::Regex),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterKind::Substring => "substring",
+            FilterKind::Glob => "glob",
+            FilterKind::Regex => "regex",
+        }
+    }
+
+    pub fn next(&self) -> FilterKind {
+        match self {
+            FilterKind::Substring => FilterKind::Glob,
+            FilterKind::Glob => FilterKind::Regex,
+            FilterKind::Regex => FilterKind::Substring,
+        }
+    }
+}
+
+enum Matcher {
+    All,
+    Substring(String),
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+// narrows the listing by file name. case is ignored unless the
+// pattern has an upper case letter, like smartcase in vim
+pub struct Filter {
+    pub kind: FilterKind,
+    pub text: String,
+    // the pattern does not compile, everything is shown meanwhile
+    pub error: Option<String>,
+    ignore_case: bool,
+    matcher: Matcher,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind) -> Filter {
+        Filter {
+            kind,
+            text: String::new(),
+            error: None,
+            ignore_case: true,
+            matcher: Matcher::All,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.text.is_empty()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.compile();
+    }
+
+    pub fn set_kind(&mut self, kind: FilterKind) {
+        self.kind = kind;
+        self.compile();
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    fn compile(&mut self) {
+        let ignore_case = !self.text.chars().any(|c| c.is_uppercase());
+        self.ignore_case = ignore_case;
+        self.error = None;
+        self.matcher = if self.text.is_empty() {
+            Matcher::All
+        } else {
+            match self.kind {
+                FilterKind::Substring if ignore_case => Matcher::Substring(self.text.to_lowercase()),
+                FilterKind::Substring => Matcher::Substring(self.text.clone()),
+                FilterKind::Glob => match Pattern::new(&self.text) {
+                    Ok(p) => Matcher::Glob(p),
+                    Err(e) => {
+                        self.error = Some(e.msg.to_string());
+                        Matcher::All
+                    }
+                },
+                FilterKind::Regex => {
+                    match RegexBuilder::new(&self.text).case_insensitive(ignore_case).build() {
+                        Ok(r) => Matcher::Regex(r),
+                        Err(_) => {
+                            self.error = Some(String::from("invalid regex"));
+                            Matcher::All
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        match &self.matcher {
+            Matcher::All => true,
+            Matcher::Substring(s) if self.ignore_case => name.to_lowercase().contains(s.as_str()),
+            Matcher::Substring(s) => name.contains(s.as_str()),
+            Matcher::Glob(p) => p.matches_with(
+                name,
+                MatchOptions {
+                    case_sensitive: !self.ignore_case,
+                    ..MatchOptions::new()
+                },
+            ),
+            Matcher::Regex(r) => r.is_match(name),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    filemanager::ReadDirItems,
+    filter::{Filter, FilterKind},
+    pathmanager::NavigationCommand,
+    walker::{WalkOptions, Walker},
+};
+
+pub struct FindOptions {
+    pub pattern: String,
+    pub max_depth: Option<usize>,
+    // Some(true) only folders, Some(false) only files
+    pub only_dirs: Option<bool>,
+    pub hide_hidden_file: bool,
+}
+
+impl FindOptions {
+    // [-d depth] [-t f|d] [-H] <pattern>, -H also searches hidden files
+    pub fn parse(args: &[String], hide_hidden_file: bool) -> Result<FindOptions, String> {
+        let mut options = FindOptions {
+            pattern: String::new(),
+            max_depth: None,
+            only_dirs: None,
+            hide_hidden_file,
+        };
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-d" => {
+                    let depth = args.next().and_then(|d| d.parse().ok());
+                    options.max_depth = Some(depth.ok_or("-d needs a number")?);
+                }
+                "-t" => {
+                    options.only_dirs = match args.next().map(|t| t.as_str()) {
+                        Some("f") => Some(false),
+                        Some("d") => Some(true),
+                        _ => return Err(String::from("-t needs f or d")),
+                    }
+                }
+                "-H" => options.hide_hidden_file = false,
+                _ if options.pattern.is_empty() => options.pattern = arg.clone(),
+                _ => return Err(format!("unexpected {}", arg)),
+            }
+        }
+
+        if options.pattern.is_empty() {
+            return Err(String::from("no pattern given"));
+        }
+        Ok(options)
+    }
+}
+
+// a name search below root, results come in while the walk goes
+pub struct Search {
+    pub root: PathBuf,
+    pub pattern: String,
+    walker: Walker,
+    filter: Filter,
+    only_dirs: Option<bool>,
+    pub results: Vec<ReadDirItems>,
+    pub scanned: usize,
+    pub cancelled: bool,
+}
+
+impl Search {
+    // a pattern with *, ? or [ is a glob, anything else a substring
+    pub fn start(root: &Path, options: FindOptions) -> Result<Search, String> {
+        let kind = if options.pattern.contains(['*', '?', '[']) {
+            FilterKind::Glob
+        } else {
+            FilterKind::Substring
+        };
+        let mut filter = Filter::new(kind);
+        filter.set_text(&options.pattern);
+        if let Some(e) = filter.error.take() {
+            return Err(e);
+        }
+
+        let walker = Walker::spawn(
+            root,
+            WalkOptions {
+                hide_hidden_file: options.hide_hidden_file,
+                max_depth: options.max_depth,
+            },
+        );
+        Ok(Search {
+            root: root.to_path_buf(),
+            pattern: options.pattern,
+            walker,
+            filter,
+            only_dirs: options.only_dirs,
+            results: Vec::new(),
+            scanned: 0,
+            cancelled: false,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.walker.done && !self.cancelled
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.walker.cancel();
+    }
+
+    // true when new results came in
+    pub fn poll(&mut self) -> bool {
+        if self.cancelled {
+            return false;
+        }
+        let found = self.walker.poll();
+        self.scanned += found.len();
+        let before = self.results.len();
+
+        for entry in found {
+            if self.only_dirs.is_some_and(|d| d != entry.is_dir) {
+                continue;
+            }
+            let file_name = entry
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            if !self.filter.matches(&file_name) {
+                continue;
+            }
+
+            let rel = entry.path.strip_prefix(&self.root).unwrap_or(&entry.path);
+            let icon = if entry.is_dir { "🖿 " } else { "📑 " };
+            let link_target = fs::read_link(&entry.path).ok();
+            let is_broken_link = link_target.is_some() && !entry.path.exists();
+            self.results.push(ReadDirItems {
+                navigation_type: NavigationCommand::Absolute(entry.path.clone()),
+                label: format!("{}{}", icon, rel.display()),
+                path: entry.path,
+                file_name,
+                is_dir: entry.is_dir,
+                link_target,
+                is_broken_link,
+            });
+        }
+        self.results.len() > before
+    }
+
+    pub fn labels(&self) -> Vec<String> {
+        self.results.iter().map(|i| i.label.clone()).collect()
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// total rank kept before every entry is aged, like zoxide does
+const MAX_RANK: f64 = 10000.0;
+
+pub struct DirEntry {
+    pub path: PathBuf,
+    pub rank: f64,
+    // unix seconds of the last visit
+    pub last: u64,
+}
+
+// every visited folder with how often and how recently it was visited
+pub struct Frecency {
+    entries: Vec<DirEntry>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Frecency {
+    pub fn new() -> Frecency {
+        Frecency {
+            entries: Vec::new(),
+        }
+    }
+
+    // one "rank|last visit|path" per line
+    pub fn parse(content: &str) -> Frecency {
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '|');
+                let rank = parts.next()?.parse().ok()?;
+                let last = parts.next()?.parse().ok()?;
+                let path = PathBuf::from(parts.next()?);
+                Some(DirEntry { path, rank, last })
+            })
+            .collect();
+        Frecency { entries }
+    }
+
+    pub fn dump(&self) -> String {
+        self.entries
+            .iter()
+            .map(|e| format!("{}|{}|{}", e.rank, e.last, e.path.display()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn visit(&mut self, path: &Path) {
+        let now = now();
+        match self.entries.iter_mut().find(|e| e.path == path) {
+            Some(entry) => {
+                entry.rank += 1.0;
+                entry.last = now;
+            }
+            None => self.entries.push(DirEntry {
+                path: path.to_path_buf(),
+                rank: 1.0,
+                last: now,
+            }),
+        }
+
+        if self.entries.iter().map(|e| e.rank).sum::<f64>() > MAX_RANK {
+            for entry in self.entries.iter_mut() {
+                entry.rank *= 0.9;
+            }
+            self.entries.retain(|e| e.rank >= 1.0);
+        }
+    }
+
+    // recent visits count more
+    fn score(entry: &DirEntry, now: u64) -> f64 {
+        let age = now.saturating_sub(entry.last);
+        let factor = match age {
+            a if a < 60 * 60 => 4.0,
+            a if a < 24 * 60 * 60 => 2.0,
+            a if a < 7 * 24 * 60 * 60 => 0.5,
+            _ => 0.25,
+        };
+        entry.rank * factor
+    }
+
+    // folders that contain every fragment in order, the last one inside
+    // the folder name. best score first, gone folders are left out
+    pub fn query(&self, fragments: &[String]) -> Vec<(PathBuf, f64)> {
+        let now = now();
+        let fragments: Vec<String> = fragments.iter().map(|f| f.to_lowercase()).collect();
+
+        let mut res: Vec<(PathBuf, f64)> = self
+            .entries
+            .iter()
+            .filter(|e| matches(&e.path, &fragments) && e.path.is_dir())
+            .map(|e| (e.path.clone(), Self::score(e, now)))
+            .collect();
+        res.sort_by(|a, b| b.1.total_cmp(&a.1));
+        res
+    }
+}
+
+fn matches(path: &Path, fragments: &[String]) -> bool {
+    let full = path.to_string_lossy().to_lowercase();
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut at = 0;
+    for fragment in fragments {
+        match full[at..].find(fragment.as_str()) {
+            Some(i) => at += i + fragment.len(),
+            None => return false,
+        }
+    }
+    fragments.last().is_none_or(|last| {
+        // a fragment that was a whole path already matched the end
+        last.contains('/') || name.contains(last.trim_end_matches('/'))
+    })
+}
@@ -0,0 +1,247 @@
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+};
+
+use super::walker::{WalkOptions, Walker};
+
+// fzy style weights
+const GAP_LEADING: f64 = -0.005;
+const GAP_TRAILING: f64 = -0.005;
+const GAP_INNER: f64 = -0.01;
+const MATCH_CONSECUTIVE: f64 = 1.0;
+const MATCH_SLASH: f64 = 0.9;
+const MATCH_WORD: f64 = 0.8;
+const MATCH_CAPITAL: f64 = 0.7;
+const MATCH_DOT: f64 = 0.6;
+
+// longer paths are only checked for a subsequence, scoring them is too slow
+const MAX_LEN: usize = 1024;
+// rows shown in the finder, worse matches are not kept
+const MAX_ROWS: usize = 500;
+
+// bonus for matching the char at i, depends on what comes before it
+fn bonus(chars: &[char], i: usize) -> f64 {
+    let Some(prev) = i.checked_sub(1).map(|p| chars[p]) else {
+        return MATCH_SLASH;
+    };
+    match prev {
+        '/' => MATCH_SLASH,
+        '-' | '_' | ' ' => MATCH_WORD,
+        '.' => MATCH_DOT,
+        _ if prev.is_lowercase() && chars[i].is_uppercase() => MATCH_CAPITAL,
+        _ => 0.0,
+    }
+}
+
+fn eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+// score of needle inside haystack and the char index of every matched char,
+// None when needle is not a subsequence of haystack. case is ignored
+pub fn score(needle: &str, haystack: &str) -> Option<(f64, Vec<usize>)> {
+    let needle: Vec<char> = needle.chars().collect();
+    let hay: Vec<char> = haystack.chars().collect();
+    let (n, m) = (needle.len(), hay.len());
+
+    // cheap check first, most candidates fail here
+    let mut it = hay.iter();
+    if !needle.iter().all(|c| it.any(|h| eq(*c, *h))) {
+        return None;
+    }
+    if n == 0 {
+        return Some((0.0, Vec::new()));
+    }
+    if n == m {
+        return Some((f64::MAX, (0..m).collect()));
+    }
+    if m > MAX_LEN {
+        return Some((f64::MIN, Vec::new()));
+    }
+
+    let bonus: Vec<f64> = (0..m).map(|j| bonus(&hay, j)).collect();
+    // d: best score ending with a match at j, mm: best score up to j
+    let mut d = vec![vec![f64::NEG_INFINITY; m]; n];
+    let mut mm = vec![vec![f64::NEG_INFINITY; m]; n];
+
+    for i in 0..n {
+        let mut prev = f64::NEG_INFINITY;
+        let gap = if i == n - 1 { GAP_TRAILING } else { GAP_INNER };
+        for j in 0..m {
+            if eq(needle[i], hay[j]) {
+                let s = if i == 0 {
+                    j as f64 * GAP_LEADING + bonus[j]
+                } else if j > 0 {
+                    (mm[i - 1][j - 1] + bonus[j]).max(d[i - 1][j - 1] + MATCH_CONSECUTIVE)
+                } else {
+                    f64::NEG_INFINITY
+                };
+                d[i][j] = s;
+                prev = s.max(prev + gap);
+            } else {
+                prev += gap;
+            }
+            mm[i][j] = prev;
+        }
+    }
+
+    // walk back from the end picking where each char matched
+    let mut positions = vec![0; n];
+    let mut must_match = false;
+    let mut j = m;
+    for i in (0..n).rev() {
+        while j > 0 {
+            j -= 1;
+            let here = d[i][j];
+            if here != f64::NEG_INFINITY && (must_match || here == mm[i][j]) {
+                must_match = i > 0
+                    && j > 0
+                    && mm[i][j] == d[i - 1][j - 1] + MATCH_CONSECUTIVE;
+                positions[i] = j;
+                break;
+            }
+        }
+    }
+
+    Some((mm[n - 1][m - 1], positions))
+}
+
+pub struct Match {
+    pub index: usize,
+    pub score: f64,
+    pub positions: Vec<usize>,
+}
+
+// best first, shorter paths win ties, then walk order
+fn order(candidates: &[String], a: &Match, b: &Match) -> Ordering {
+    b.score
+        .total_cmp(&a.score)
+        .then(candidates[a.index].len().cmp(&candidates[b.index].len()))
+        .then(a.index.cmp(&b.index))
+}
+
+// drop all but the best MAX_ROWS, without sorting the rest
+fn keep_best(candidates: &[String], matches: &mut Vec<Match>) {
+    if matches.len() > MAX_ROWS {
+        matches.select_nth_unstable_by(MAX_ROWS - 1, |a, b| order(candidates, a, b));
+        matches.truncate(MAX_ROWS);
+    }
+}
+
+// every path below root, ranked against the query as the walk goes
+pub struct Finder {
+    pub root: PathBuf,
+    walker: Walker,
+    // relative to root, folders end with "/"
+    candidates: Vec<String>,
+    query: String,
+    // every candidate the query matches, in walk order
+    hits: Vec<usize>,
+    // the best of them, best first
+    matches: Vec<Match>,
+}
+
+impl Finder {
+    pub fn open(root: &Path, hide_hidden_file: bool) -> Finder {
+        let walker = Walker::spawn(
+            root,
+            WalkOptions {
+                hide_hidden_file,
+                max_depth: None,
+            },
+        );
+        Finder {
+            root: root.to_path_buf(),
+            walker,
+            candidates: Vec::new(),
+            query: String::new(),
+            hits: Vec::new(),
+            matches: Vec::new(),
+        }
+    }
+
+    pub fn is_walking(&self) -> bool {
+        !self.walker.done
+    }
+
+    pub fn total(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn matched(&self) -> usize {
+        self.hits.len()
+    }
+
+    // true when new candidates came in
+    pub fn poll(&mut self) -> bool {
+        let found = self.walker.poll();
+        if found.is_empty() {
+            return false;
+        }
+        let start = self.candidates.len();
+        for entry in found {
+            let Ok(rel) = entry.path.strip_prefix(&self.root) else {
+                continue;
+            };
+            let mut rel = rel.to_string_lossy().to_string();
+            if entry.is_dir {
+                rel.push('/');
+            }
+            self.candidates.push(rel);
+        }
+        self.rank((start..self.candidates.len()).collect());
+        true
+    }
+
+    // false when the query did not change
+    pub fn set_query(&mut self, query: &str) -> bool {
+        if self.query == query {
+            return false;
+        }
+        // typing on only narrows the hits, the rest can't match anymore
+        let pool = if query.starts_with(&self.query) {
+            std::mem::take(&mut self.hits)
+        } else {
+            self.hits.clear();
+            (0..self.candidates.len()).collect()
+        };
+        self.query = query.to_string();
+        self.matches.clear();
+        self.rank(pool);
+        true
+    }
+
+    // scores the candidates in `pool` and merges them in
+    fn rank(&mut self, pool: Vec<usize>) {
+        let candidates = &self.candidates;
+        for index in pool {
+            if let Some((score, positions)) = score(&self.query, &candidates[index]) {
+                self.hits.push(index);
+                self.matches.push(Match {
+                    index,
+                    score,
+                    positions,
+                });
+                // trimmed on the way so a short query doesn't keep every path
+                if self.matches.len() >= 2 * MAX_ROWS {
+                    keep_best(candidates, &mut self.matches);
+                }
+            }
+        }
+        keep_best(candidates, &mut self.matches);
+        self.matches.sort_by(|a, b| order(candidates, a, b));
+    }
+
+    pub fn rows(&self) -> Vec<(String, Vec<usize>)> {
+        self.matches
+            .iter()
+            .map(|m| (self.candidates[m.index].clone(), m.positions.clone()))
+            .collect()
+    }
+
+    pub fn path(&self, row: usize) -> Option<PathBuf> {
+        let m = self.matches.get(row)?;
+        Some(self.root.join(self.candidates[m.index].trim_end_matches('/')))
+    }
+}
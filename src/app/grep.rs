@@ -0,0 +1,234 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+};
+
+use ignore::{WalkBuilder, WalkState};
+use regex::{Regex, RegexBuilder};
+
+use super::filemanager;
+
+// past this many hits the search stops, nobody scrolls that far
+const MAX_HITS: usize = 10000;
+// files bigger than this are not read
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_SNIPPET: usize = 300;
+
+pub struct GrepHit {
+    pub path: PathBuf,
+    // 1 based like editors count
+    pub line: usize,
+    pub snippet: String,
+    // char indexes of the matched text inside snippet
+    pub matched: Vec<usize>,
+}
+
+pub struct GrepOptions {
+    pub pattern: String,
+    pub ignore_case: bool,
+    pub respect_gitignore: bool,
+    pub hide_hidden_file: bool,
+}
+
+impl GrepOptions {
+    // [-i] [-u] [-H] <regex>, -u also searches what .gitignore leaves out
+    pub fn parse(
+        args: &[String],
+        respect_gitignore: bool,
+        hide_hidden_file: bool,
+    ) -> Result<GrepOptions, String> {
+        let mut options = GrepOptions {
+            pattern: String::new(),
+            ignore_case: false,
+            respect_gitignore,
+            hide_hidden_file,
+        };
+
+        for arg in args {
+            match arg.as_str() {
+                "-i" => options.ignore_case = true,
+                "-u" => options.respect_gitignore = false,
+                "-H" => options.hide_hidden_file = false,
+                _ if options.pattern.is_empty() => options.pattern = arg.clone(),
+                _ => return Err(format!("unexpected {}", arg)),
+            }
+        }
+
+        if options.pattern.is_empty() {
+            return Err(String::from("no pattern given"));
+        }
+        Ok(options)
+    }
+}
+
+fn clean(line: &str) -> String {
+    line.trim().replace('\t', "    ").chars().take(MAX_SNIPPET).collect()
+}
+
+// every matching line of a text file, nothing for binary files
+fn search_file(path: &Path, regex: &Regex) -> Vec<GrepHit> {
+    let too_big = fs::metadata(path).map_or(true, |m| m.len() > MAX_FILE_SIZE);
+    let data = if too_big { None } else { fs::read(path).ok() };
+    let Some(data) = data.filter(|d| !filemanager::is_binary(d)) else {
+        return Vec::new();
+    };
+
+    let text = String::from_utf8_lossy(&data);
+    let mut res = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if !regex.is_match(line) {
+            continue;
+        }
+        let snippet = clean(line);
+        let mut matched = Vec::new();
+        for m in regex.find_iter(&snippet) {
+            let start = snippet[..m.start()].chars().count();
+            matched.extend(start..start + m.as_str().chars().count());
+        }
+        res.push(GrepHit {
+            path: path.to_path_buf(),
+            line: i + 1,
+            snippet,
+            matched,
+        });
+    }
+    res
+}
+
+// a content search below root on a pool of threads, hits come in per file
+pub struct Grep {
+    pub root: PathBuf,
+    pub pattern: String,
+    rx: Receiver<Vec<GrepHit>>,
+    cancel: Arc<AtomicBool>,
+    files: Arc<AtomicUsize>,
+    pub hits: Vec<GrepHit>,
+    done: bool,
+    pub cancelled: bool,
+    // stopped at MAX_HITS
+    pub truncated: bool,
+}
+
+impl Grep {
+    pub fn start(root: &Path, options: GrepOptions) -> Result<Grep, String> {
+        let regex = RegexBuilder::new(&options.pattern)
+            .case_insensitive(options.ignore_case)
+            .build()
+            .map_err(|_| String::from("invalid regex"))?;
+
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let files = Arc::new(AtomicUsize::new(0));
+        let (cancel_flag, files_count) = (cancel.clone(), files.clone());
+        let respect = options.respect_gitignore;
+        let walker = WalkBuilder::new(root)
+            .hidden(options.hide_hidden_file)
+            .git_ignore(respect)
+            .git_global(respect)
+            .git_exclude(respect)
+            .ignore(respect)
+            .parents(respect)
+            // .gitignore counts outside of a repository too
+            .require_git(false)
+            .build_parallel();
+
+        thread::spawn(move || {
+            walker.run(|| {
+                let (tx, regex) = (tx.clone(), regex.clone());
+                let (cancel_flag, files_count) = (cancel_flag.clone(), files_count.clone());
+                Box::new(move |entry| {
+                    if cancel_flag.load(Ordering::Relaxed) {
+                        return WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    if !entry.file_type().is_some_and(|t| t.is_file()) {
+                        return WalkState::Continue;
+                    }
+
+                    files_count.fetch_add(1, Ordering::Relaxed);
+                    let hits = search_file(entry.path(), &regex);
+                    if !hits.is_empty() && tx.send(hits).is_err() {
+                        return WalkState::Quit;
+                    }
+                    WalkState::Continue
+                })
+            });
+        });
+
+        Ok(Grep {
+            root: root.to_path_buf(),
+            pattern: options.pattern,
+            rx,
+            cancel,
+            files,
+            hits: Vec::new(),
+            done: false,
+            cancelled: false,
+            truncated: false,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.done && !self.cancelled
+    }
+
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn files_searched(&self) -> usize {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    // true when new hits came in
+    pub fn poll(&mut self) -> bool {
+        let before = self.hits.len();
+        while !self.cancelled {
+            match self.rx.try_recv() {
+                Ok(mut hits) => self.hits.append(&mut hits),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+            if self.hits.len() >= MAX_HITS {
+                self.hits.truncate(MAX_HITS);
+                self.truncated = true;
+                self.cancel();
+            }
+        }
+        self.hits.len() > before
+    }
+
+    // "path:line: snippet" and the chars to highlight in it
+    pub fn rows(&self) -> Vec<(String, Vec<usize>)> {
+        self.hits
+            .iter()
+            .map(|hit| {
+                let rel = hit.path.strip_prefix(&self.root).unwrap_or(&hit.path);
+                let prefix = format!("{}:{}: ", rel.display(), hit.line);
+                let offset = prefix.chars().count();
+                (
+                    format!("{}{}", prefix, hit.snippet),
+                    hit.matched.iter().map(|i| i + offset).collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl Drop for Grep {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use super::{
+    filemanager,
+    trash::{self, TrashItem},
+};
+
+// every file operation luru does itself, so it can be undone
+pub enum Operation {
+    Create {
+        path: PathBuf,
+        is_dir: bool,
+        // folders made on the way, removed again on undo when empty
+        parents: Vec<PathBuf>,
+    },
+    Move { from: PathBuf, to: PathBuf },
+    RenameAll { renames: Vec<(PathBuf, PathBuf)> },
+    Copy { from: PathBuf, to: PathBuf },
+    Trash { item: TrashItem },
+    Link {
+        target: PathBuf,
+        path: PathBuf,
+        hard: bool,
+    },
+}
+
+impl Operation {
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Create { path, .. } => format!("create {}", path.display()),
+            Operation::Move { from, to } => format!("move {} to {}", from.display(), to.display()),
+            Operation::RenameAll { renames } => format!("rename of {} item(s)", renames.len()),
+            Operation::Copy { from, to } => format!("copy {} to {}", from.display(), to.display()),
+            Operation::Trash { item } => format!("trash {}", item.original_path.display()),
+            Operation::Link { target, path, .. } => {
+                format!("link {} -> {}", path.display(), target.display())
+            }
+        }
+    }
+}
+
+// size and mtime of what an operation left behind
+#[derive(PartialEq)]
+struct Stamp(u64, Option<SystemTime>);
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Stamp> {
+        fs::symlink_metadata(path)
+            .ok()
+            .map(|m| Stamp(m.len(), m.modified().ok()))
+    }
+}
+
+struct Entry {
+    op: Operation,
+    stamps: Vec<Option<Stamp>>,
+}
+
+pub struct Journal {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+    // bumped on every record, undo and redo
+    changes: usize,
+}
+
+impl Journal {
+    pub fn new() -> Journal {
+        Journal {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            changes: 0,
+        }
+    }
+
+    pub fn changes(&self) -> usize {
+        self.changes
+    }
+
+    pub fn create(&mut self, path: &Path, is_dir: bool) -> io::Result<()> {
+        let parents = filemanager::missing_parents(path);
+        Self::make(path, is_dir)?;
+        self.record(Operation::Create {
+            path: path.to_path_buf(),
+            is_dir,
+            parents,
+        });
+        Ok(())
+    }
+
+    fn make(path: &Path, is_dir: bool) -> io::Result<()> {
+        if is_dir {
+            filemanager::make_dir(path)
+        } else {
+            filemanager::make_empty_file(path)
+        }
+    }
+
+    pub fn rename(&mut self, path: &Path, new_name: &str) -> io::Result<PathBuf> {
+        let to = filemanager::rename_path(path, new_name)?;
+        self.record(Operation::Move {
+            from: path.to_path_buf(),
+            to: to.clone(),
+        });
+        Ok(to)
+    }
+
+    // what could not be put back after an error is recorded, so it can be undone
+    pub fn rename_all(&mut self, renames: Vec<(PathBuf, PathBuf)>) -> io::Result<()> {
+        match filemanager::rename_all(&renames) {
+            Ok(_) => {
+                self.record(Operation::RenameAll { renames });
+                Ok(())
+            }
+            Err(partial) if partial.done.is_empty() => Err(partial.error),
+            Err(partial) => {
+                let msg = format!(
+                    "{}, {} renamed before it, :undo reverts them",
+                    partial.error,
+                    partial.done.len()
+                );
+                self.record(Operation::RenameAll {
+                    renames: partial.done,
+                });
+                Err(io::Error::other(msg))
+            }
+        }
+    }
+
+    pub fn trash_path(&mut self, path: &Path) -> io::Result<()> {
+        let item = trash::trash_path(path)?;
+        self.record(Operation::Trash { item });
+        Ok(())
+    }
+
+    pub fn link(&mut self, target: &Path, path: &Path, hard: bool) -> io::Result<()> {
+        filemanager::make_link(target, path, hard)?;
+        self.record(Operation::Link {
+            target: target.to_path_buf(),
+            path: path.to_path_buf(),
+            hard,
+        });
+        Ok(())
+    }
+
+    // for operations done elsewhere, like a background copy
+    pub fn record(&mut self, op: Operation) {
+        let stamps = Self::stamps(&op);
+        self.undo.push(Entry { op, stamps });
+        self.redo.clear();
+        self.changes += 1;
+    }
+
+    // the paths an operation produced, checked before undoing it
+    fn result_paths(op: &Operation) -> Vec<&Path> {
+        match op {
+            Operation::Create { path, .. } | Operation::Link { path, .. } => vec![path],
+            Operation::Move { to, .. } | Operation::Copy { to, .. } => vec![to],
+            Operation::RenameAll { renames } => renames.iter().map(|(_, to)| to.as_path()).collect(),
+            Operation::Trash { .. } => Vec::new(),
+        }
+    }
+
+    fn stamps(op: &Operation) -> Vec<Option<Stamp>> {
+        Self::result_paths(op).into_iter().map(Stamp::of).collect()
+    }
+
+    // returns what was undone
+    pub fn undo(&mut self) -> io::Result<String> {
+        let Some(entry) = self.undo.pop() else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "nothing to undo"));
+        };
+
+        for (path, stamp) in Self::result_paths(&entry.op).iter().zip(entry.stamps.iter()) {
+            // the entry is dropped, it can never be undone safely again
+            if Stamp::of(path) != *stamp {
+                let msg = format!("{} changed on disk, cannot undo", path.display());
+                return Err(io::Error::other(msg));
+            }
+        }
+
+        let r = match &entry.op {
+            Operation::Create {
+                path,
+                is_dir,
+                parents,
+            } => {
+                let r = if *is_dir {
+                    fs::remove_dir(path)
+                } else {
+                    fs::remove_file(path)
+                };
+                for parent in parents.iter().rev() {
+                    if fs::remove_dir(parent).is_err() {
+                        break;
+                    }
+                }
+                r
+            }
+            Operation::Move { from, to } => {
+                Self::ensure_free(from).and_then(|_| filemanager::move_path(to, from))
+            }
+            Operation::RenameAll { renames } => {
+                let back: Vec<(PathBuf, PathBuf)> =
+                    renames.iter().map(|(from, to)| (to.clone(), from.clone())).collect();
+                Self::ensure_free_targets(&back)
+                    .and_then(|_| filemanager::rename_all(&back).map_err(|p| p.error))
+            }
+            Operation::Copy { to, .. } => trash::trash_path(to).map(|_| ()),
+            Operation::Trash { item } => trash::restore(item).map(|_| ()),
+            Operation::Link { path, .. } => fs::remove_file(path),
+        };
+
+        match r {
+            Ok(_) => {
+                let msg = format!("undo {}", entry.op.describe());
+                self.redo.push(entry);
+                self.changes += 1;
+                Ok(msg)
+            }
+            Err(e) => {
+                self.undo.push(entry);
+                Err(e)
+            }
+        }
+    }
+
+    // returns what was redone
+    pub fn redo(&mut self) -> io::Result<String> {
+        let Some(mut entry) = self.redo.pop() else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "nothing to redo"));
+        };
+
+        let mut retrashed = None;
+        let r = match &entry.op {
+            Operation::Create { path, is_dir, .. } => {
+                Self::ensure_free(path).and_then(|_| Self::make(path, *is_dir))
+            }
+            Operation::Move { from, to } => {
+                Self::ensure_free(to).and_then(|_| filemanager::move_path(from, to))
+            }
+            Operation::RenameAll { renames } => {
+                Self::ensure_free_targets(renames)
+                    .and_then(|_| filemanager::rename_all(renames).map_err(|p| p.error))
+            }
+            Operation::Copy { from, to } => {
+                Self::ensure_free(to).and_then(|_| filemanager::copy_path(from, to))
+            }
+            Operation::Link { target, path, hard } => {
+                Self::ensure_free(path).and_then(|_| filemanager::make_link(target, path, *hard))
+            }
+            Operation::Trash { item } => trash::trash_path(&item.original_path).map(|item| {
+                retrashed = Some(item);
+            }),
+        };
+        if let Some(item) = retrashed {
+            entry.op = Operation::Trash { item };
+        }
+
+        match r {
+            Ok(_) => {
+                let msg = format!("redo {}", entry.op.describe());
+                entry.stamps = Self::stamps(&entry.op);
+                self.undo.push(entry);
+                self.changes += 1;
+                Ok(msg)
+            }
+            Err(e) => {
+                self.redo.push(entry);
+                Err(e)
+            }
+        }
+    }
+
+    fn ensure_free(path: &Path) -> io::Result<()> {
+        if path.symlink_metadata().is_ok() {
+            let msg = format!("{} already exists", path.display());
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, msg));
+        }
+        Ok(())
+    }
+
+    // only paths the batch moves away itself may be taken, like in a swap
+    fn ensure_free_targets(renames: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+        let sources: HashSet<&PathBuf> = renames.iter().map(|(from, _)| from).collect();
+        renames
+            .iter()
+            .filter(|(_, to)| !sources.contains(to))
+            .try_for_each(|(_, to)| Self::ensure_free(to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    #[test]
+    fn undo_and_redo_a_create() {
+        let dir = TempDir::new("journal-create");
+        let mut journal = Journal::new();
+        let path = dir.join("a/b/file");
+
+        journal.create(&path, false).unwrap();
+        assert!(path.is_file());
+        journal.undo().unwrap();
+        // the folders made on the way go too
+        assert!(!dir.join("a").exists());
+        journal.redo().unwrap();
+        assert!(path.is_file());
+    }
+
+    #[test]
+    fn undo_refuses_a_changed_result() {
+        let dir = TempDir::new("journal-changed");
+        let mut journal = Journal::new();
+        fs::write(dir.join("a"), "a").unwrap();
+
+        journal.rename(&dir.join("a"), "b").unwrap();
+        fs::write(dir.join("b"), "edited since").unwrap();
+        assert!(journal.undo().is_err());
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "edited since");
+        // dropped, it can never be undone safely
+        assert!(journal.undo().is_err());
+    }
+
+    #[test]
+    fn undo_and_redo_refuse_to_overwrite() {
+        let dir = TempDir::new("journal-move");
+        let mut journal = Journal::new();
+        fs::write(dir.join("a"), "a").unwrap();
+
+        journal.rename(&dir.join("a"), "b").unwrap();
+        fs::write(dir.join("a"), "new a").unwrap();
+        assert!(journal.undo().is_err());
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "new a");
+
+        fs::remove_file(dir.join("a")).unwrap();
+        journal.undo().unwrap();
+        fs::write(dir.join("b"), "new b").unwrap();
+        assert!(journal.redo().is_err());
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "new b");
+    }
+
+    #[test]
+    fn undo_bulk_rename_keeps_a_recreated_file() {
+        let dir = TempDir::new("journal-bulk");
+        let mut journal = Journal::new();
+        fs::write(dir.join("a"), "a").unwrap();
+        fs::write(dir.join("b"), "b").unwrap();
+
+        journal
+            .rename_all(vec![(dir.join("a"), dir.join("x")), (dir.join("b"), dir.join("y"))])
+            .unwrap();
+        fs::write(dir.join("a"), "recreated").unwrap();
+
+        assert!(journal.undo().is_err());
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "recreated");
+        assert_eq!(fs::read_to_string(dir.join("x")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.join("y")).unwrap(), "b");
+    }
+
+    #[test]
+    fn undo_and_redo_a_bulk_swap() {
+        let dir = TempDir::new("journal-swap");
+        let mut journal = Journal::new();
+        fs::write(dir.join("a"), "a").unwrap();
+        fs::write(dir.join("b"), "b").unwrap();
+        let swap = vec![(dir.join("a"), dir.join("b")), (dir.join("b"), dir.join("a"))];
+
+        journal.rename_all(swap).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "b");
+        journal.undo().unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "a");
+        journal.redo().unwrap();
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "b");
+    }
+}
@@ -10,3 +10,11 @@ pub fn wait_for_keyboard() -> io::Result<Event> {
         }
     }
 }
+
+// like wait_for_keyboard but gives up after `timeout`
+pub fn poll_keyboard(timeout: time::Duration) -> io::Result<Option<Event>> {
+    if poll(timeout)? {
+        return Ok(Some(read()?));
+    }
+    Ok(None)
+}
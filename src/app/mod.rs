@@ -1,7 +1,29 @@
+pub mod archive;
+pub mod bulkrename;
+pub mod checksum;
 pub mod config;
+pub mod conflict;
 pub mod core;
+pub mod diff;
+pub mod dirsize;
+pub mod diskusage;
+pub mod dupes;
 pub mod filemanager;
+pub mod filter;
+pub mod find;
+pub mod frecency;
+pub mod grep;
+pub mod fuzzy;
+pub mod journal;
 pub mod keyboard;
+pub mod navhistory;
 pub mod pathmanager;
+pub mod permissions;
 pub mod termin;
+#[cfg(test)]
+pub mod testutil;
+pub mod trash;
+pub mod tree;
 pub mod ui;
+pub mod walker;
+pub mod worker;
@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+use super::archive;
+
+// folders visited in this session, like the back and forward buttons of a browser
+pub struct NavHistory {
+    back: Vec<PathBuf>,
+    forward: Vec<PathBuf>,
+    current: Option<PathBuf>,
+}
+
+impl NavHistory {
+    pub fn new() -> NavHistory {
+        NavHistory {
+            back: Vec::new(),
+            forward: Vec::new(),
+            current: None,
+        }
+    }
+
+    // called on every directory change, a jump done by back() or forward()
+    // lands on `current` already and is not recorded again
+    pub fn arrive(&mut self, path: &Path) {
+        if self.current.as_deref() == Some(path) {
+            return;
+        }
+        if let Some(current) = self.current.replace(path.to_path_buf()) {
+            self.back.push(current);
+            self.forward.clear();
+        }
+    }
+
+    pub fn back(&mut self) -> Option<PathBuf> {
+        Self::step(&mut self.back, &mut self.forward, &mut self.current)
+    }
+
+    pub fn forward(&mut self) -> Option<PathBuf> {
+        Self::step(&mut self.forward, &mut self.back, &mut self.current)
+    }
+
+    // folders removed since they were visited are skipped
+    fn step(
+        from: &mut Vec<PathBuf>,
+        to: &mut Vec<PathBuf>,
+        current: &mut Option<PathBuf>,
+    ) -> Option<PathBuf> {
+        while let Some(path) = from.pop() {
+            if path.is_dir() || archive::split_path(&path).is_some() {
+                if let Some(current) = current.replace(path.clone()) {
+                    to.push(current);
+                }
+                return Some(path);
+            }
+        }
+        None
+    }
+}
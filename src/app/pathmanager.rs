@@ -93,3 +93,133 @@ pub fn resolve_path(current_dir: &Path, command: &NavigationCommand) -> io::Resu
         }
     }
 }
+
+// a path given to a command, "../x" is x next to current_dir
+// (convert_path_to_nav would read it as a plain "go up")
+pub fn resolve_arg(current_dir: &Path, arg: &str) -> io::Result<PathBuf> {
+    let arg = if arg == "$HOME" { "~" } else { arg };
+    resolve_path(current_dir, &NavigationCommand::Relative(PathBuf::from(arg)))
+}
+
+// split command arguments by whitespace, keeping "quoted parts" together
+pub fn split_args(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut has_arg = false;
+
+    for ch in input.chars() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(ch);
+                has_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if has_arg {
+                    args.push(current.clone());
+                    current.clear();
+                    has_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+// "{a,b}.txt" -> ["a.txt", "b.txt"], groups can be repeated or nested
+pub fn expand_braces(input: &str) -> Vec<String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut depth = 0;
+    let mut open = None;
+    let mut commas = Vec::new();
+
+    for (i, ch) in chars.iter().enumerate() {
+        match ch {
+            '{' => {
+                if depth == 0 {
+                    open = Some(i);
+                    commas.clear();
+                }
+                depth += 1;
+            }
+            ',' if depth == 1 => commas.push(i),
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth > 0 {
+                    continue;
+                }
+                let Some(start) = open else { continue };
+                if commas.is_empty() {
+                    continue;
+                }
+
+                let prefix: String = chars[..start].iter().collect();
+                let suffix: String = chars[i + 1..].iter().collect();
+                let mut bounds = vec![start];
+                bounds.extend(commas.iter());
+                bounds.push(i);
+
+                let mut res = Vec::new();
+                for w in bounds.windows(2) {
+                    let alt: String = chars[w[0] + 1..w[1]].iter().collect();
+                    for expanded in expand_braces(&format!("{}{}{}", prefix, alt, suffix)) {
+                        res.push(expanded);
+                    }
+                }
+                return res;
+            }
+            _ => {}
+        }
+    }
+
+    vec![input.to_string()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_arg_keeps_the_name_after_dots() {
+        let cwd = Path::new("/a/b/c");
+        assert_eq!(resolve_arg(cwd, "../sibling").unwrap(), PathBuf::from("/a/b/sibling"));
+        assert_eq!(resolve_arg(cwd, "../../x").unwrap(), PathBuf::from("/a/x"));
+        assert_eq!(resolve_arg(cwd, "..").unwrap(), PathBuf::from("/a/b"));
+        assert_eq!(resolve_arg(cwd, "new").unwrap(), PathBuf::from("/a/b/c/new"));
+        assert_eq!(resolve_arg(cwd, "/srv/x").unwrap(), PathBuf::from("/srv/x"));
+    }
+
+    #[test]
+    fn split_args_keeps_quoted_parts_together() {
+        assert_eq!(split_args("a  b\tc"), ["a", "b", "c"]);
+        assert_eq!(split_args("\"my file\" 'it''s' x\"y z\""), ["my file", "its", "xy z"]);
+        assert_eq!(split_args("a \"\" b"), ["a", "", "b"]);
+        assert!(split_args("   ").is_empty());
+    }
+
+    #[test]
+    fn expand_braces_expands_every_group() {
+        assert_eq!(expand_braces("{a,b}.txt"), ["a.txt", "b.txt"]);
+        assert_eq!(expand_braces("{a,b}/{c,d}"), ["a/c", "a/d", "b/c", "b/d"]);
+        assert_eq!(expand_braces("x{a,b{1,2}}"), ["xa", "xb1", "xb2"]);
+        assert_eq!(expand_braces("src/{lib,bin}/"), ["src/lib/", "src/bin/"]);
+    }
+
+    #[test]
+    fn expand_braces_leaves_other_braces_alone() {
+        assert_eq!(expand_braces("{a}.txt"), ["{a}.txt"]);
+        assert_eq!(expand_braces("{a,b"), ["{a,b"]);
+        assert_eq!(expand_braces("plain"), ["plain"]);
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    fs, io,
+    os::unix::fs::{chown, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use super::filemanager;
+
+pub const SETUID: u32 = 0o4000;
+pub const SETGID: u32 = 0o2000;
+pub const STICKY: u32 = 0o1000;
+
+// rows of the permission dialog, in display order
+pub const BITS: [(&str, u32); 12] = [
+    ("user   read", 0o400),
+    ("user   write", 0o200),
+    ("user   exec", 0o100),
+    ("group  read", 0o040),
+    ("group  write", 0o020),
+    ("group  exec", 0o010),
+    ("other  read", 0o004),
+    ("other  write", 0o002),
+    ("other  exec", 0o001),
+    ("setuid", SETUID),
+    ("setgid", SETGID),
+    ("sticky", STICKY),
+];
+
+// "rwxr-x---" style, with s/t in the exec columns
+pub fn mode_string(mode: u32) -> String {
+    let mut res = String::new();
+    for (i, (_, bit)) in BITS.iter().take(9).enumerate() {
+        let special = match i {
+            2 => mode & SETUID != 0,
+            5 => mode & SETGID != 0,
+            8 => mode & STICKY != 0,
+            _ => false,
+        };
+        let (set, unset) = if i == 8 { ('t', 'T') } else { ('s', 'S') };
+        let ch = match (i % 3, mode & bit != 0, special) {
+            (0, true, _) => 'r',
+            (1, true, _) => 'w',
+            (2, true, true) => set,
+            (2, false, true) => unset,
+            (2, true, false) => 'x',
+            _ => '-',
+        };
+        res.push(ch);
+    }
+    res
+}
+
+// name:x:id:... lines of /etc/passwd and /etc/group
+fn read_id_db(path: &str) -> Vec<(String, u32)> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((name.to_string(), id))
+        })
+        .collect()
+}
+
+pub fn user_name(uid: u32) -> String {
+    read_id_db("/etc/passwd")
+        .into_iter()
+        .find(|(_, id)| *id == uid)
+        .map(|(name, _)| name)
+        .unwrap_or(uid.to_string())
+}
+
+pub fn group_name(gid: u32) -> String {
+    read_id_db("/etc/group")
+        .into_iter()
+        .find(|(_, id)| *id == gid)
+        .map(|(name, _)| name)
+        .unwrap_or(gid.to_string())
+}
+
+// a name or a numeric id
+pub fn user_id(name: &str) -> Option<u32> {
+    name.parse().ok().or_else(|| {
+        read_id_db("/etc/passwd")
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, id)| id)
+    })
+}
+
+pub fn group_id(name: &str) -> Option<u32> {
+    name.parse().ok().or_else(|| {
+        read_id_db("/etc/group")
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, id)| id)
+    })
+}
+
+// turn the `set` bits on and the `clear` bits off, every other bit of
+// each file stays as it was. failing files are returned instead of stopping
+pub fn apply(
+    path: &Path,
+    set: u32,
+    clear: u32,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    recursive: bool,
+) -> Vec<(PathBuf, io::Error)> {
+    let mut failures = Vec::new();
+    apply_to(path, set, clear, uid, gid, recursive, &mut failures);
+    failures
+}
+
+fn apply_to(
+    path: &Path,
+    set: u32,
+    clear: u32,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    recursive: bool,
+    failures: &mut Vec<(PathBuf, io::Error)>,
+) {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(e) => {
+            failures.push((path.to_path_buf(), e));
+            return;
+        }
+    };
+
+    // chmod would follow the link and change its target
+    if meta.file_type().is_symlink() {
+        return;
+    }
+
+    let mode = meta.permissions().mode() & 0o7777;
+    // a folder gets new bits before and loses bits after its children,
+    // so clearing u+rx doesn't lock the walk out of it
+    if recursive && meta.is_dir() {
+        if set & !mode != 0 {
+            let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode | set));
+        }
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                for entry in entries.flatten() {
+                    apply_to(&entry.path(), set, clear, uid, gid, recursive, failures);
+                }
+            }
+            Err(e) => failures.push((path.to_path_buf(), e)),
+        }
+    }
+
+    let mode = (mode & !clear) | set;
+    let r = fs::set_permissions(path, fs::Permissions::from_mode(mode)).and_then(|_| {
+        if uid.is_some() || gid.is_some() {
+            chown(path, uid, gid)
+        } else {
+            Ok(())
+        }
+    });
+    if let Err(e) = r {
+        failures.push((path.to_path_buf(), e));
+    }
+}
+
+// state of the permission dialog opened with :perm
+pub struct PermDialog {
+    pub path: PathBuf,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub recursive: bool,
+    pub failures: Vec<String>,
+    is_dir: bool,
+    // mode on disk, what differs from it is what the user toggled
+    orig_mode: u32,
+    orig_uid: u32,
+    orig_gid: u32,
+}
+
+const ROW_RECURSIVE: usize = BITS.len() + 2;
+const ROW_APPLY: usize = BITS.len() + 3;
+
+impl PermDialog {
+    pub fn open(path: &Path) -> io::Result<PermDialog> {
+        let meta = filemanager::metadata(&path.to_path_buf())?;
+        Ok(PermDialog {
+            path: path.to_path_buf(),
+            mode: meta.mode & 0o7777,
+            uid: meta.uid,
+            gid: meta.gid,
+            recursive: false,
+            failures: Vec::new(),
+            is_dir: meta.is_dir,
+            orig_mode: meta.mode & 0o7777,
+            orig_uid: meta.uid,
+            orig_gid: meta.gid,
+        })
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        let check = |on: bool| if on { "[x]" } else { "[ ]" };
+        let mut rows: Vec<String> = BITS
+            .iter()
+            .map(|(label, bit)| format!("{} {}", check(self.mode & bit != 0), label))
+            .collect();
+
+        rows.push(format!(
+            "    owner  {}  (:chown <user>)",
+            user_name(self.uid)
+        ));
+        rows.push(format!(
+            "    group  {}  (:chgrp <group>)",
+            group_name(self.gid)
+        ));
+        if self.is_dir {
+            rows.push(format!(
+                "{} apply to everything inside",
+                check(self.recursive)
+            ));
+        } else {
+            rows.push(String::from("    -"));
+        }
+        rows.push(format!("--> apply {}", mode_string(self.mode)));
+
+        for failure in self.failures.iter() {
+            rows.push(format!("failed: {}", failure));
+        }
+        rows
+    }
+
+    // toggle the row under the cursor, true means "apply" was picked
+    pub fn activate(&mut self, row: usize) -> bool {
+        match row {
+            r if r < BITS.len() => self.mode ^= BITS[r].1,
+            ROW_RECURSIVE if self.is_dir => self.recursive = !self.recursive,
+            ROW_APPLY => return true,
+            // owner and group are changed with :chown / :chgrp
+            _ => {}
+        }
+        false
+    }
+
+    // returns how many files failed
+    pub fn apply(&mut self) -> usize {
+        // only chown when asked to, it usually needs root
+        let uid = (self.uid != self.orig_uid).then_some(self.uid);
+        let gid = (self.gid != self.orig_gid).then_some(self.gid);
+
+        let set = self.mode & !self.orig_mode;
+        let clear = self.orig_mode & !self.mode;
+
+        self.failures = apply(&self.path, set, clear, uid, gid, self.recursive)
+            .iter()
+            .map(|(path, e)| format!("{}: {}", path.display(), e))
+            .collect();
+
+        if self.failures.is_empty() {
+            self.orig_mode = self.mode;
+            self.orig_uid = self.uid;
+            self.orig_gid = self.gid;
+        }
+        self.failures.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    fn mode_of(path: &Path) -> u32 {
+        fs::symlink_metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    fn set_mode(path: &Path, mode: u32) {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn mode_string_shows_special_bits() {
+        assert_eq!(mode_string(0o754), "rwxr-xr--");
+        assert_eq!(mode_string(0o4755), "rwsr-xr-x");
+        assert_eq!(mode_string(0o2644), "rw-r-Sr--");
+        assert_eq!(mode_string(0o1777), "rwxrwxrwt");
+    }
+
+    #[test]
+    fn recursive_apply_only_changes_toggled_bits() {
+        let dir = TempDir::new("perm-masks");
+        let folder = dir.join("d");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("f"), "").unwrap();
+        set_mode(&folder.join("f"), 0o644);
+        set_mode(&folder, 0o700);
+
+        assert!(apply(&folder, 0o050, 0o000, None, None, true).is_empty());
+        assert_eq!(mode_of(&folder), 0o750);
+        assert_eq!(mode_of(&folder.join("f")), 0o654);
+
+        assert!(apply(&folder, 0o000, 0o600, None, None, true).is_empty());
+        assert_eq!(mode_of(&folder.join("f")), 0o054);
+        set_mode(&folder, 0o700);
+    }
+
+    #[test]
+    fn recursive_apply_reaches_children_before_locking_the_folder() {
+        let dir = TempDir::new("perm-order");
+        let folder = dir.join("d");
+        fs::create_dir_all(folder.join("sub")).unwrap();
+        fs::write(folder.join("sub/f"), "").unwrap();
+        set_mode(&folder.join("sub/f"), 0o644);
+        set_mode(&folder.join("sub"), 0o755);
+        set_mode(&folder, 0o755);
+
+        assert!(apply(&folder, 0o000, 0o500, None, None, true).is_empty());
+        assert_eq!(mode_of(&folder.join("sub/f")), 0o244);
+        assert_eq!(mode_of(&folder), 0o255);
+
+        // and back on, the folder opens before its children are walked
+        assert!(apply(&folder, 0o500, 0o000, None, None, true).is_empty());
+        assert_eq!(mode_of(&folder.join("sub/f")), 0o744);
+    }
+
+    #[test]
+    fn links_are_left_alone() {
+        let dir = TempDir::new("perm-link");
+        fs::create_dir(dir.join("d")).unwrap();
+        fs::write(dir.join("target"), "").unwrap();
+        set_mode(&dir.join("target"), 0o600);
+        std::os::unix::fs::symlink(dir.join("target"), dir.join("d/link")).unwrap();
+
+        assert!(apply(&dir.join("d"), 0o044, 0o000, None, None, true).is_empty());
+        assert_eq!(mode_of(&dir.join("target")), 0o600);
+    }
+}
@@ -1,13 +1,19 @@
 use std::{
-    io,
+    fs, io,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
-pub struct Termin {}
+use super::filemanager;
+
+pub struct Termin {
+    // the folder the last command ended in
+    pub running_path: Option<PathBuf>,
+}
 
 impl Termin {
     pub fn new() -> Termin {
-        Termin {}
+        Termin { running_path: None }
     }
 
     pub fn run(&mut self, input: String) -> io::Result<bool> {
@@ -17,6 +23,9 @@ impl Termin {
 
         let mut arg = input.clone();
         arg.push_str(" && pwd");
+        // a file of ours, written on its own line so a trailing comment can't swallow it
+        let (pwd_file, _) = filemanager::make_temp_file("luru-pwd-", "")?;
+        arg.push_str("\npwd > \"$LURU_PWD_FILE\"");
 
         let stdin = Stdio::inherit();
         let stdout = Stdio::inherit();
@@ -24,6 +33,7 @@ impl Termin {
         let output = Command::new("sh")
             .arg("-c")
             .arg(arg)
+            .env("LURU_PWD_FILE", &pwd_file)
             .stdin(stdin)
             .stdout(stdout)
             .spawn();
@@ -36,6 +46,24 @@ impl Termin {
                 eprintln!("{}", e);
             }
         };
+
+        self.running_path = fs::read_to_string(&pwd_file)
+            .ok()
+            .map(|p| PathBuf::from(p.trim_end_matches('\n')))
+            .filter(|p| p.is_dir());
+        let _ = fs::remove_file(&pwd_file);
         Ok(true)
     }
 }
+
+// opens path in $EDITOR (vi when unset) at line and waits for it
+pub fn edit(path: &Path, line: usize) -> io::Result<()> {
+    Command::new("sh")
+        .arg("-c")
+        .arg("${EDITOR:-vi} +\"$1\" \"$2\"")
+        .arg("sh")
+        .arg(line.to_string())
+        .arg(path)
+        .status()?;
+    Ok(())
+}
@@ -0,0 +1,381 @@
+// freedesktop.org trash: https://specifications.freedesktop.org/trash-spec/
+use std::{
+    env, fs, io,
+    os::unix::fs::{DirBuilderExt, MetadataExt},
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, NaiveDateTime};
+
+use super::filemanager;
+
+#[derive(Clone)]
+pub struct TrashItem {
+    // the trash the item is in, the home one or a .Trash-$uid of its filesystem
+    pub trash: PathBuf,
+    // name inside Trash/files, also Trash/info/<name>.trashinfo
+    pub name: String,
+    pub original_path: PathBuf,
+    pub deletion_date: String,
+}
+
+pub fn trash_dir() -> io::Result<PathBuf> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var("HOME")
+                .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
+            PathBuf::from(home).join(".local/share")
+        }
+    };
+
+    let trash = data_home.join("Trash");
+    fs::create_dir_all(trash.join("files"))?;
+    fs::create_dir_all(trash.join("info"))?;
+    Ok(trash)
+}
+
+// files on another filesystem go to $topdir/.Trash-$uid there, a move
+// into the home trash would copy them
+fn trash_for(path: &Path) -> io::Result<PathBuf> {
+    let home = trash_dir()?;
+    let parent = path.parent().unwrap_or(path);
+    let dev = fs::symlink_metadata(parent)?.dev();
+    if fs::metadata(&home)?.dev() == dev {
+        return Ok(home);
+    }
+
+    let topdir = parent
+        .ancestors()
+        .take_while(|p| fs::symlink_metadata(p).is_ok_and(|m| m.dev() == dev))
+        .last()
+        .unwrap_or(parent);
+    let uid = current_uid()?;
+    let trash = topdir.join(format!(".Trash-{}", uid));
+
+    match fs::symlink_metadata(&trash) {
+        Ok(meta) if meta.is_dir() && meta.uid() == uid => {}
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} is not a trash of ours", trash.display()),
+            ))
+        }
+        Err(_) => fs::DirBuilder::new().mode(0o700).create(&trash)?,
+    }
+    fs::create_dir_all(trash.join("files"))?;
+    fs::create_dir_all(trash.join("info"))?;
+    Ok(trash)
+}
+
+// the owner of /proc/self is the user running us
+fn current_uid() -> io::Result<u32> {
+    Ok(fs::metadata("/proc/self")?.uid())
+}
+
+pub fn trash_path(path: &Path) -> io::Result<TrashItem> {
+    let original_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()?.join(path)
+    };
+
+    trash_into(&trash_for(&original_path)?, original_path)
+}
+
+fn trash_into(trash: &Path, original_path: PathBuf) -> io::Result<TrashItem> {
+    let file_name = original_path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash this path"))?
+        .to_string_lossy()
+        .into_owned();
+
+    let deletion_date = Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+    let content = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_path(&original_path),
+        deletion_date
+    );
+
+    // the .trashinfo file is created first, with create_new, to claim the name
+    let mut name = file_name.clone();
+    let mut n = 1;
+    let info_path = loop {
+        let info_path = trash.join("info").join(format!("{}.trashinfo", name));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(_) => break info_path,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                n += 1;
+                name = format!("{}.{}", file_name, n);
+            }
+            Err(e) => return Err(e),
+        }
+    };
+
+    let r = fs::write(&info_path, content)
+        .and_then(|_| filemanager::move_path(&original_path, &trash.join("files").join(&name)));
+    if let Err(e) = r {
+        let _ = fs::remove_file(&info_path);
+        return Err(e);
+    }
+
+    Ok(TrashItem {
+        trash: trash.to_path_buf(),
+        name,
+        original_path,
+        deletion_date,
+    })
+}
+
+// the home trash and the .Trash-$uid of every mounted filesystem, newest
+// first, with the number of .trashinfo files that could not be read
+pub fn list() -> io::Result<(Vec<TrashItem>, usize)> {
+    let mut trashes = vec![trash_dir()?];
+    let uid = current_uid()?;
+    for topdir in mount_points() {
+        let trash = topdir.join(format!(".Trash-{}", uid));
+        if fs::symlink_metadata(&trash).is_ok_and(|m| m.is_dir() && m.uid() == uid) {
+            trashes.push(trash);
+        }
+    }
+
+    let mut res = Vec::new();
+    let mut skipped = 0;
+    for trash in trashes.iter() {
+        skipped += list_in(trash, &mut res)?;
+    }
+
+    res.sort_by(|a, b| {
+        let parse = |d: &str| NaiveDateTime::parse_from_str(d, "%Y-%m-%dT%H:%M:%S").ok();
+        parse(&b.deletion_date).cmp(&parse(&a.deletion_date))
+    });
+    Ok((res, skipped))
+}
+
+fn list_in(trash: &Path, res: &mut Vec<TrashItem>) -> io::Result<usize> {
+    let mut skipped = 0;
+
+    for entry in fs::read_dir(trash.join("info"))? {
+        let info_path = entry?.path();
+        let Some(name) = info_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_suffix(".trashinfo"))
+        else {
+            continue;
+        };
+
+        // one broken .trashinfo should not hide the rest of the trash
+        let Ok(content) = filemanager::read_file(&info_path) else {
+            skipped += 1;
+            continue;
+        };
+        let mut original_path = None;
+        let mut deletion_date = String::new();
+        for line in content.lines() {
+            if let Some(v) = line.strip_prefix("Path=") {
+                original_path = Some(PathBuf::from(decode_path(v)));
+            } else if let Some(v) = line.strip_prefix("DeletionDate=") {
+                deletion_date = v.to_string();
+            }
+        }
+
+        match original_path {
+            // relative paths are relative to the folder holding the trash
+            Some(original_path) => res.push(TrashItem {
+                trash: trash.to_path_buf(),
+                name: name.to_string(),
+                original_path: trash.parent().unwrap_or(trash).join(original_path),
+                deletion_date,
+            }),
+            None => skipped += 1,
+        }
+    }
+
+    Ok(skipped)
+}
+
+// second field of /proc/self/mounts, spaces and such come as \040 octal
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+
+    mounts
+        .lines()
+        .filter_map(|line| line.split(' ').nth(1))
+        .filter(|dir| *dir != "/")
+        .map(|dir| {
+            let mut res = String::new();
+            let mut rest = dir;
+            while let Some(i) = rest.find('\\') {
+                res.push_str(&rest[..i]);
+                match u8::from_str_radix(rest.get(i + 1..i + 4).unwrap_or(""), 8) {
+                    Ok(b) => {
+                        res.push(b as char);
+                        rest = &rest[i + 4..];
+                    }
+                    Err(_) => {
+                        res.push('\\');
+                        rest = &rest[i + 1..];
+                    }
+                }
+            }
+            res.push_str(rest);
+            PathBuf::from(res)
+        })
+        .collect()
+}
+
+pub fn restore(item: &TrashItem) -> io::Result<PathBuf> {
+    let trash = &item.trash;
+    let target = &item.original_path;
+
+    if target.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    filemanager::move_path(&trash.join("files").join(&item.name), target)?;
+    fs::remove_file(info_path(trash, item))?;
+    Ok(target.clone())
+}
+
+pub fn purge(item: &TrashItem) -> io::Result<()> {
+    let trash = &item.trash;
+    let file = trash.join("files").join(&item.name);
+
+    if file.symlink_metadata().is_ok() {
+        filemanager::remove_path(&file)?;
+    }
+    fs::remove_file(info_path(trash, item))
+}
+
+fn info_path(trash: &Path, item: &TrashItem) -> PathBuf {
+    trash.join("info").join(format!("{}.trashinfo", item.name))
+}
+
+// Path= is stored url-encoded
+fn encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut res = String::new();
+    for b in path.as_os_str().as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                res.push(*b as char)
+            }
+            _ => res.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    res
+}
+
+fn decode_path(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                res.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        res.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&res).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::testutil::TempDir;
+
+    fn make_trash(dir: &TempDir) -> PathBuf {
+        let trash = dir.join("Trash");
+        fs::create_dir_all(trash.join("files")).unwrap();
+        fs::create_dir_all(trash.join("info")).unwrap();
+        trash
+    }
+
+    #[test]
+    fn trash_list_and_restore() {
+        let dir = TempDir::new("trash-restore");
+        let trash = make_trash(&dir);
+        let path = dir.join("a file%.txt");
+
+        fs::write(&path, "one").unwrap();
+        let first = trash_into(&trash, path.clone()).unwrap();
+        fs::write(&path, "two").unwrap();
+        let second = trash_into(&trash, path.clone()).unwrap();
+        // the same name twice gets a number
+        assert_eq!(first.name, "a file%.txt");
+        assert_eq!(second.name, "a file%.txt.2");
+
+        let mut items = Vec::new();
+        assert_eq!(list_in(&trash, &mut items).unwrap(), 0);
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|i| i.original_path == path));
+
+        restore(&second).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two");
+        // the original name is taken again
+        assert!(restore(&first).is_err());
+        assert!(trash.join("files").join(&first.name).exists());
+
+        purge(&first).unwrap();
+        items.clear();
+        list_in(&trash, &mut items).unwrap();
+        assert!(items.is_empty());
+        assert_eq!(fs::read_dir(trash.join("files")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn list_skips_broken_trashinfo() {
+        let dir = TempDir::new("trash-broken");
+        let trash = make_trash(&dir);
+        let path = dir.join("kept");
+
+        fs::write(&path, "").unwrap();
+        trash_into(&trash, path.clone()).unwrap();
+        fs::write(trash.join("info/nopath.trashinfo"), "[Trash Info]\n").unwrap();
+        fs::create_dir(trash.join("info/folder.trashinfo")).unwrap();
+
+        let mut items = Vec::new();
+        assert_eq!(list_in(&trash, &mut items).unwrap(), 2);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].original_path, path);
+    }
+
+    #[test]
+    fn relative_paths_are_below_the_trash_folder() {
+        let dir = TempDir::new("trash-relative");
+        let trash = dir.join(".Trash-1000");
+        fs::create_dir_all(trash.join("files")).unwrap();
+        fs::create_dir_all(trash.join("info")).unwrap();
+        fs::write(
+            trash.join("info/x.trashinfo"),
+            "[Trash Info]\nPath=sub/x%20y\nDeletionDate=2024-01-02T03:04:05\n",
+        )
+        .unwrap();
+
+        let mut items = Vec::new();
+        list_in(&trash, &mut items).unwrap();
+        assert_eq!(items[0].original_path, dir.join("sub/x y"));
+    }
+}
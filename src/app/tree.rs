@@ -0,0 +1,92 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use super::filemanager::ReadDirItems;
+
+// folders expanded in the tree view, remembered for every root folder
+pub struct TreeState {
+    expanded: HashMap<PathBuf, HashSet<PathBuf>>,
+}
+
+// where a row of the flattened tree sits
+pub struct TreeRow {
+    pub depth: usize,
+    // guides and the expand marker drawn before the row
+    pub guide: String,
+}
+
+impl TreeState {
+    pub fn new() -> TreeState {
+        TreeState {
+            expanded: HashMap::new(),
+        }
+    }
+
+    pub fn is_expanded(&self, root: &Path, path: &Path) -> bool {
+        self.expanded.get(root).is_some_and(|e| e.contains(path))
+    }
+
+    pub fn expand(&mut self, root: &Path, path: &Path) {
+        self.expanded
+            .entry(root.to_path_buf())
+            .or_default()
+            .insert(path.to_path_buf());
+    }
+
+    // false when it was not expanded
+    pub fn collapse(&mut self, root: &Path, path: &Path) -> bool {
+        self.expanded
+            .get_mut(root)
+            .is_some_and(|e| e.remove(path))
+    }
+
+    // `items` with the children of every expanded folder under it.
+    // children are only read here, so a folder never expanded is never read
+    pub fn flatten(
+        &self,
+        root: &Path,
+        items: Vec<ReadDirItems>,
+        read: &mut dyn FnMut(&Path) -> Vec<ReadDirItems>,
+    ) -> (Vec<ReadDirItems>, Vec<TreeRow>) {
+        let mut res = (Vec::new(), Vec::new());
+        self.push_level(root, items, 0, "", read, &mut res);
+        res
+    }
+
+    fn push_level(
+        &self,
+        root: &Path,
+        items: Vec<ReadDirItems>,
+        depth: usize,
+        prefix: &str,
+        read: &mut dyn FnMut(&Path) -> Vec<ReadDirItems>,
+        res: &mut (Vec<ReadDirItems>, Vec<TreeRow>),
+    ) {
+        let count = items.len();
+        for (i, item) in items.into_iter().enumerate() {
+            let (branch, child_prefix) = match (depth, i + 1 == count) {
+                (0, _) => ("", String::new()),
+                (_, true) => ("└─ ", format!("{}   ", prefix)),
+                (_, false) => ("├─ ", format!("{}│  ", prefix)),
+            };
+            let is_folder = item.is_dir && item.file_name != "../";
+            let expanded = is_folder && self.is_expanded(root, &item.path);
+            let marker = match (is_folder, expanded) {
+                (true, true) => "▾ ",
+                (true, false) => "▸ ",
+                _ if item.file_name == "../" => "",
+                _ => "  ",
+            };
+
+            let children = if expanded { read(&item.path) } else { Vec::new() };
+            res.1.push(TreeRow {
+                depth,
+                guide: format!("{}{}{}", prefix, branch, marker),
+            });
+            res.0.push(item);
+            self.push_level(root, children, depth + 1, &child_prefix, read, res);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     path::PathBuf,
 };
 
+use super::archive;
 use crossterm::{
     cursor,
     execute,
@@ -22,6 +23,35 @@ fn get_char_len(arg: &str) -> usize {
     arg.chars().collect::<Vec<_>>().len()
 }
 
+// how a single row of render_content is drawn
+#[derive(Clone, Default)]
+pub struct RowStyle {
+    pub marked: bool,
+    // ansi color used instead of the dimmed default
+    pub color: Option<&'static str>,
+    // char indexes drawn bold, like the matched chars of a search
+    pub highlight: Vec<usize>,
+    // drawn dimmed before the row, like the guides of the tree view
+    pub prefix: String,
+}
+
+// wraps every highlighted char, `base` is the row color it returns to
+fn highlight_chars(val: &str, highlight: &[usize], base: &str) -> String {
+    if highlight.is_empty() {
+        return val.to_string();
+    }
+    val.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if highlight.contains(&i) {
+                format!("\x1b[0m\x1b[1m\x1b[4m{}\x1b[0m{}", c, base)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
 // #[derive(Clone, Copy)]
 pub struct UI {
     pub stdout: Stdout,
@@ -166,7 +196,7 @@ impl UI {
         // self.clear_screen()?;
         self.move_cursor(0, 0)?;
 
-        let path_color = if self.path_label.exists() {
+        let path_color = if self.path_label.exists() || archive::split_path(&self.path_label).is_some() {
             style::Color::DarkGreen
         } else {
             style::Color::DarkRed
@@ -214,7 +244,7 @@ impl UI {
         Ok(())
     }
 
-    pub fn render_content(&mut self, content: &Vec<String>) -> io::Result<()> {
+    pub fn render_content(&mut self, content: &[String], styles: &[RowStyle]) -> io::Result<()> {
         // self.clear_screen();
         self.render_frame()?;
 
@@ -243,7 +273,12 @@ impl UI {
                 continue;
             };
 
-            let p = get_char_len(val_r).min(self.window_size.0 as usize);
+            let row_style = styles.get(i).cloned().unwrap_or_default();
+            let prefix_len = get_char_len(&row_style.prefix).min(self.window_size.0 as usize);
+            let prefix = str_slince(&row_style.prefix, 0, prefix_len);
+            let width = self.window_size.0 as usize - prefix_len;
+
+            let p = get_char_len(val_r).min(width);
 
             let mut val = format!(
                 "{}",
@@ -251,12 +286,8 @@ impl UI {
                 str_slince(val_r, 0, p)
             );
 
-            if p < self.window_size.0 as usize {
-                val.push_str(
-                    String::from(" ")
-                        .repeat(self.window_size.0 as usize - p)
-                        .as_str(),
-                );
+            if p < width {
+                val.push_str(String::from(" ").repeat(width - p).as_str());
             }
 
             let x = 0;
@@ -267,21 +298,23 @@ impl UI {
             };
 
             self.move_cursor(x, y)?;
-            if i == self.content_cursor as usize {
-                execute!(
-                    self.stdout,
-                    // SetForegroundColor(style::Color::White),
-                    style::Print("\x1b[95m\x1b[1m"),
-                    style::Print(val),
-                    style::ResetColor,
-                )?;
+            let color = if row_style.marked {
+                if i == self.content_cursor {
+                    "\x1b[93m\x1b[1m"
+                } else {
+                    "\x1b[33m"
+                }
+            } else if i == self.content_cursor {
+                "\x1b[95m\x1b[1m"
             } else {
-                execute!(
-                    self.stdout,
-                    style::ResetColor,
-                    style::Print(format!("\x1b[2m{}\x1b[0m", val))
-                )?;
-            }
+                row_style.color.unwrap_or("\x1b[2m")
+            };
+            let val = highlight_chars(&val, &row_style.highlight, color);
+            execute!(
+                self.stdout,
+                style::ResetColor,
+                style::Print(format!("\x1b[2m{}\x1b[0m{}{}\x1b[0m", prefix, color, val))
+            )?;
         }
 
         let render_from_sub_render_items =
@@ -0,0 +1,113 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub struct WalkOptions {
+    pub hide_hidden_file: bool,
+    // 1 is only the children of root
+    pub max_depth: Option<usize>,
+}
+
+pub struct WalkEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+// walks a tree on its own thread and hands out what it found in batches.
+// shallow entries come first, links are not followed
+pub struct Walker {
+    rx: Receiver<Vec<WalkEntry>>,
+    cancel: Arc<AtomicBool>,
+    pub done: bool,
+}
+
+impl Walker {
+    pub fn spawn(root: &Path, options: WalkOptions) -> Walker {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+        let root = root.to_path_buf();
+
+        thread::spawn(move || {
+            let mut queue = VecDeque::from([(root, 0)]);
+            let mut batch = Vec::new();
+            let mut sent = Instant::now();
+
+            while let Some((dir, depth)) = queue.pop_front() {
+                if cancel_flag.load(Ordering::Relaxed) {
+                    return;
+                }
+                if options.max_depth.is_some_and(|max| depth >= max) {
+                    continue;
+                }
+                let Ok(entries) = fs::read_dir(&dir) else {
+                    continue;
+                };
+
+                for entry in entries.flatten() {
+                    if options.hide_hidden_file && entry.file_name().to_string_lossy().starts_with('.') {
+                        continue;
+                    }
+                    let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                    if is_dir {
+                        queue.push_back((entry.path(), depth + 1));
+                    }
+                    batch.push(WalkEntry {
+                        path: entry.path(),
+                        is_dir,
+                    });
+                }
+
+                // small batches keep the list moving without flooding the channel
+                if batch.len() >= 1024 || sent.elapsed() > Duration::from_millis(50) {
+                    if tx.send(std::mem::take(&mut batch)).is_err() {
+                        return;
+                    }
+                    sent = Instant::now();
+                }
+            }
+            let _ = tx.send(batch);
+        });
+
+        Walker {
+            rx,
+            cancel,
+            done: false,
+        }
+    }
+
+    // everything found since the last call
+    pub fn poll(&mut self) -> Vec<WalkEntry> {
+        let mut res = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(mut batch) => res.append(&mut batch),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        res
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Walker {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
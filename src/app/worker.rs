@@ -0,0 +1,284 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Instant,
+};
+
+use super::{diskusage::DuNode, dupes::DupeGroup, filemanager, journal::Operation, trash};
+
+enum JobEvent {
+    Total(u64),
+    Progress(u64, PathBuf),
+    Finished(JobReport),
+}
+
+// one item of a copy or move
+pub struct Transfer {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    // the existing target goes to the trash first
+    pub replace: bool,
+}
+
+pub struct JobReport {
+    // what was done, in order, ready for the journal
+    pub ops: Vec<Operation>,
+    pub done: usize,
+    pub errors: Vec<String>,
+    pub cancelled: bool,
+}
+
+// a copy or move running on its own thread, polled by App::run
+pub struct Job {
+    pub is_move: bool,
+    pub skipped: usize,
+    rx: Receiver<JobEvent>,
+    cancel: Arc<AtomicBool>,
+    started: Instant,
+    done_bytes: u64,
+    total_bytes: u64,
+    current: PathBuf,
+}
+
+impl Job {
+    pub fn spawn_transfer(pairs: Vec<Transfer>, is_move: bool, skipped: usize) -> Job {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+
+        thread::spawn(move || transfer(pairs, is_move, tx, cancel_flag));
+
+        Job {
+            is_move,
+            skipped,
+            rx,
+            cancel,
+            started: Instant::now(),
+            done_bytes: 0,
+            total_bytes: 0,
+            current: PathBuf::new(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    // block until the worker is done
+    pub fn wait(&mut self) -> Option<JobReport> {
+        while let Ok(event) = self.rx.recv() {
+            if let JobEvent::Finished(report) = event {
+                return Some(report);
+            }
+        }
+        None
+    }
+
+    // take in what the worker sent, the report comes back once it is finished
+    pub fn poll(&mut self) -> Option<JobReport> {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                JobEvent::Total(total) => self.total_bytes = total,
+                JobEvent::Progress(bytes, current) => {
+                    self.done_bytes += bytes;
+                    self.current = current;
+                }
+                JobEvent::Finished(report) => return Some(report),
+            }
+        }
+        None
+    }
+
+    // [#####-----] 50% 1.0 GiB/2.0 GiB 80.0 MiB/s ETA 0:12 name
+    pub fn status_line(&self) -> String {
+        let percent = (self.done_bytes * 100)
+            .checked_div(self.total_bytes)
+            .unwrap_or(0)
+            .min(100);
+        let filled = (percent / 10) as usize;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            self.done_bytes as f64 / elapsed
+        } else {
+            0.0
+        };
+        let eta = if rate > 0.0 {
+            let secs = (self.total_bytes.saturating_sub(self.done_bytes) as f64 / rate) as u64;
+            format!("{}:{:02}", secs / 60, secs % 60)
+        } else {
+            String::from("-:--")
+        };
+
+        format!(
+            "\x1b[36m[{}{}] {}% {}/{} {}/s ETA {}\x1b[0m {} {} \x1b[1m\x1b[035m^x\x1b[0m cancel",
+            "#".repeat(filled),
+            "-".repeat(10 - filled),
+            percent,
+            filemanager::human_size(self.done_bytes),
+            filemanager::human_size(self.total_bytes),
+            filemanager::human_size(rate as u64),
+            eta,
+            if self.is_move { "move" } else { "copy" },
+            self.current
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy(),
+        )
+    }
+}
+
+fn transfer(pairs: Vec<Transfer>, is_move: bool, tx: Sender<JobEvent>, cancel: Arc<AtomicBool>) {
+    let total = pairs.iter().map(|t| filemanager::tree_size(&t.from)).sum();
+    let _ = tx.send(JobEvent::Total(total));
+
+    let mut report = JobReport {
+        ops: Vec::new(),
+        done: 0,
+        errors: Vec::new(),
+        cancelled: false,
+    };
+
+    for Transfer { from, to, replace } in pairs {
+        if cancel.load(Ordering::Relaxed) {
+            report.cancelled = true;
+            break;
+        }
+
+        if to.symlink_metadata().is_ok() {
+            if !replace {
+                report
+                    .errors
+                    .push(format!("{} already exists", to.display()));
+                continue;
+            }
+            match trash::trash_path(&to) {
+                Ok(item) => report.ops.push(Operation::Trash { item }),
+                Err(e) => {
+                    report
+                        .errors
+                        .push(format!("cannot replace {}: {}", to.display(), e));
+                    continue;
+                }
+            }
+        }
+
+        let mut progress = |bytes: u64| {
+            let _ = tx.send(JobEvent::Progress(bytes, from.clone()));
+            !cancel.load(Ordering::Relaxed)
+        };
+        let r = if is_move {
+            filemanager::move_path_with(&from, &to, &mut progress)
+        } else {
+            filemanager::copy_path_with(&from, &to, &mut progress)
+        };
+
+        match r {
+            Ok(_) => {
+                report.done += 1;
+                report.ops.push(if is_move {
+                    Operation::Move { from, to }
+                } else {
+                    Operation::Copy { from, to }
+                });
+            }
+            Err(e) => {
+                // a cancelled copy leaves a half written target
+                if cancel.load(Ordering::Relaxed) {
+                    let _ = filemanager::remove_path(&to);
+                    report.cancelled = true;
+                    break;
+                }
+                report.errors.push(format!("{}: {}", from.display(), e));
+            }
+        }
+    }
+
+    let _ = tx.send(JobEvent::Finished(report));
+}
+
+enum ScanEvent {
+    Progress(PathBuf),
+    // None when it was cancelled
+    Finished(Option<Scanned>),
+}
+
+// what a scan hands back
+pub enum Scanned {
+    Usage(DuNode),
+    Dupes(Vec<DupeGroup>),
+}
+
+// a read only walk (:du, :dupes) running on its own thread, polled by App::run
+pub struct Scan {
+    rx: Receiver<ScanEvent>,
+    cancel: Arc<AtomicBool>,
+    count: usize,
+    current: PathBuf,
+}
+
+impl Scan {
+    // `work` gets a progress callback that returns false once cancelled
+    pub fn spawn<F>(work: F) -> Scan
+    where
+        F: FnOnce(&mut dyn FnMut(&Path) -> bool) -> Scanned + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_flag = cancel.clone();
+
+        thread::spawn(move || {
+            let mut progress = |p: &Path| {
+                let _ = tx.send(ScanEvent::Progress(p.to_path_buf()));
+                !cancel_flag.load(Ordering::Relaxed)
+            };
+            let res = work(&mut progress);
+            let res = (!cancel_flag.load(Ordering::Relaxed)).then_some(res);
+            let _ = tx.send(ScanEvent::Finished(res));
+        });
+
+        Scan {
+            rx,
+            cancel,
+            count: 0,
+            current: PathBuf::new(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    // Some once the worker is done, holding None when it was cancelled
+    pub fn poll(&mut self) -> Option<Option<Scanned>> {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                ScanEvent::Progress(current) => {
+                    self.count += 1;
+                    self.current = current;
+                }
+                ScanEvent::Finished(res) => return Some(res),
+            }
+        }
+        None
+    }
+
+    // scanning 1234: /path ^x cancel
+    pub fn status_line(&self) -> String {
+        format!(
+            "\x1b[36mscanning {}:\x1b[0m {} \x1b[1m\x1b[035m^x\x1b[0m cancel",
+            self.count,
+            self.current.display(),
+        )
+    }
+}
+
+impl Drop for Scan {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}